@@ -0,0 +1,100 @@
+//! Hashing loop throughput: `double_sha256` over the serialized header
+//! against the midstate `HeaderHasher` and every available `HashBackend`.
+//!
+//! Run with `cargo bench --bench hashing`.
+
+use std::{hint::black_box, time::Instant};
+
+use essentia_mining_plugin::{
+    BlockHeader, HashTarget, double_sha256,
+    r#impl::{
+        Avx2Backend, HashBackend, HeaderHasher, ScalarBackend, ShaNiBackend, Sse41Backend,
+        decode_hex,
+    },
+};
+
+const NONCES: u32 = 2_000_000;
+
+fn header() -> BlockHeader {
+    let mut merkle_root =
+        decode_hex("4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b")
+            .expect("valid hex");
+    merkle_root.reverse();
+    BlockHeader {
+        version:         1,
+        prev_block_hash: [0; 32],
+        merkle_root:     merkle_root.try_into().expect("32 bytes"),
+        timestamp:       1_231_006_505,
+        bits:            0x1d00_ffff,
+        nonce:           0,
+    }
+}
+
+/// Hashes per second of `run`, which hashes `NONCES` nonces.
+fn measure(name: &str, run: impl Fn() -> u32) -> f64 {
+    // Warm up caches and the branch predictor.
+    black_box(run());
+
+    let start = Instant::now();
+    let found = black_box(run());
+    let rate = f64::from(NONCES) / start.elapsed().as_secs_f64();
+    println!("{name:<24} {:>10.3} MH/s  ({found} shares)", rate / 1e6);
+    rate
+}
+
+fn main() {
+    let template = header();
+    let target = HashTarget::from_difficulty(1.0);
+
+    let baseline = measure("double_sha256", || {
+        let mut header = template.clone();
+        let mut found = 0;
+        for nonce in 0..NONCES {
+            header.nonce = nonce;
+            let mut hash = double_sha256(&header.serialize());
+            hash.reverse();
+            found += u32::from(target.is_valid_hash(&hash));
+        }
+        found
+    });
+
+    let hasher = HeaderHasher::new(&template);
+    let full = measure("HeaderHasher::hash", || {
+        let mut found = 0;
+        for nonce in 0..NONCES {
+            let mut hash = hasher.hash(nonce);
+            hash.reverse();
+            found += u32::from(target.is_valid_hash(&hash));
+        }
+        found
+    });
+
+    let early_exit = measure("HeaderHasher::check", || {
+        (0..NONCES).map(|nonce| u32::from(hasher.check(nonce, &target).is_some())).sum()
+    });
+
+    let mut backends: Vec<Box<dyn HashBackend>> = vec![Box::new(ScalarBackend)];
+    backends.extend(Sse41Backend::detect().map(|b| Box::new(b) as Box<dyn HashBackend>));
+    backends.extend(Avx2Backend::detect().map(|b| Box::new(b) as Box<dyn HashBackend>));
+    backends.extend(ShaNiBackend::detect().map(|b| Box::new(b) as Box<dyn HashBackend>));
+    let mut rates = Vec::new();
+    for backend in &backends {
+        let lanes = backend.lanes() as u32;
+        let rate = measure(&format!("HashBackend {}", backend.name()), || {
+            let mut found = 0;
+            for first in (0..NONCES).step_by(lanes as usize) {
+                let count = lanes.min(NONCES - first) as usize;
+                backend.scan(&hasher, first, count, &target, &mut |_, _| found += 1);
+            }
+            found
+        });
+        rates.push((backend.name(), rate));
+    }
+
+    println!();
+    println!("midstate speedup:   {:.2}x", full / baseline);
+    println!("early-exit speedup: {:.2}x", early_exit / baseline);
+    for (name, rate) in rates {
+        println!("{:<19} {:.2}x", format!("{name} speedup:"), rate / baseline);
+    }
+}
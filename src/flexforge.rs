@@ -15,6 +15,8 @@ use essentia_traits::plugin_contracts::{
     FlexForgePanelInfo, StreamingCapable, UiConfigurable,
 };
 
+use crate::r#impl::MiningConfig;
+
 /// Mining Plugin FlexForge integration.
 #[derive(Debug)]
 pub struct MiningPluginFlexForge {
@@ -114,6 +116,13 @@ impl MiningPluginFlexForge {
         }
     }
 
+    /// Applies the UI temperature limit to `base` as its thermal throttling
+    /// limit.
+    #[must_use]
+    pub fn mining_config(&self, base: MiningConfig) -> MiningConfig {
+        base.with_thermal_limit(Some(self.config.temp_limit))
+    }
+
     /// Updates statistics from mining coordinator.
     pub fn update_stats(&mut self, stats: MiningDisplayStats) {
         self.stats = stats;
@@ -226,7 +235,7 @@ impl UiConfigurable for MiningPluginFlexForge {
             )
             .with_field(
                 ConfigField::number("temp_limit", "Temperature Limit (°C)", 80.0, 50.0, 95.0)
-                    .with_description("Maximum allowed GPU temperature")
+                    .with_description("Maximum allowed CPU/GPU temperature")
                     .with_group("Safety"),
             )
     }
@@ -409,6 +418,19 @@ mod tests {
         assert!(panel.on_config_changed("invalid_key", "value").is_err());
     }
 
+    #[test]
+    fn test_mining_config_uses_temp_limit() {
+        let mut panel = MiningPluginFlexForge::new();
+        panel.on_config_changed("temp_limit", "72").expect("test assertion");
+        panel.on_config_changed("cpu_threads", "3").expect("test assertion");
+
+        let base = MiningConfig::default();
+        let config = panel.mining_config(base.clone());
+        assert_eq!(config.thermal_throttle_temp, Some(72));
+        assert_eq!(config.thread_count, base.thread_count);
+        assert_eq!(config.worker_name, base.worker_name);
+    }
+
     #[test]
     fn test_streaming() {
         let mut panel = MiningPluginFlexForge::new();
@@ -0,0 +1,264 @@
+//! CPU and memory limits of the process's control group.
+//!
+//! In a container the host's core count and memory say little about what
+//! the miner may use. [`CgroupLimits::from_root`] reads `/proc/self/cgroup`
+//! and then, under the standard `/sys/fs/cgroup` mounts:
+//!
+//! | Limit        | cgroup v2                         | cgroup v1                                  |
+//! |--------------|-----------------------------------|--------------------------------------------|
+//! | CPU quota    | `cpu.max`                         | `cpu.cfs_quota_us` / `cpu.cfs_period_us`   |
+//! | CPU set      | `cpuset.cpus.effective`           | `cpuset.effective_cpus` or `cpuset.cpus`   |
+//! | Memory limit | `memory.max`                      | `memory.limit_in_bytes`                    |
+//! | Memory usage | `memory.current`                  | `memory.usage_in_bytes`                    |
+//!
+//! A controller listed on a v1 hierarchy is read there; everything else
+//! comes from the unified v2 hierarchy, which covers hybrid setups. Quotas
+//! and limits of parent groups apply too, so the group and each ancestor
+//! below the mount are read and the tightest value wins. When a container
+//! sees the host's group path but has its own group mounted at the root,
+//! only the mount root exists and is used.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::r#impl::topology::parse_cpu_list;
+
+/// v1 reports "no limit" as the largest page-aligned `i64`; treat anything
+/// this large as unlimited.
+const UNLIMITED_MEMORY: u64 = 1 << 60;
+
+/// Resource limits imposed on this process by its cgroup.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CgroupLimits {
+    /// CPU time the group may use, in CPUs (quota over period).
+    pub cpu_quota:    Option<f64>,
+    /// CPUs the group may run on.
+    pub cpuset:       Option<Vec<usize>>,
+    /// Memory limit in bytes.
+    pub memory_limit: Option<u64>,
+    /// Memory the group currently uses, in bytes.
+    pub memory_usage: Option<u64>,
+}
+
+/// Where a controller's files live.
+enum Hierarchy {
+    V1(PathBuf),
+    V2(PathBuf),
+}
+
+impl CgroupLimits {
+    /// Limits of the calling process; none off Linux.
+    #[must_use]
+    pub fn detect() -> Self {
+        #[cfg(target_os = "linux")]
+        {
+            Self::from_root(Path::new("/"))
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Self::default()
+        }
+    }
+
+    /// Read the limits from the `proc` and `sys` trees under `root`.
+    ///
+    /// Missing files mean no limit, so this never fails.
+    #[must_use]
+    pub fn from_root(root: &Path) -> Self {
+        let Ok(membership) = fs::read_to_string(root.join("proc/self/cgroup")) else {
+            return Self::default();
+        };
+        let mount = root.join("sys/fs/cgroup");
+
+        let mut limits = Self::default();
+        if let Some(groups) = hierarchy(&membership, &mount, "cpu") {
+            limits.cpu_quota = tightest(&groups, cpu_quota);
+        }
+        if let Some(groups) = hierarchy(&membership, &mount, "cpuset") {
+            limits.cpuset = groups.iter().find_map(cpuset);
+        }
+        if let Some(groups) = hierarchy(&membership, &mount, "memory") {
+            limits.memory_limit = tightest(&groups, memory_limit);
+            limits.memory_usage = groups.iter().find_map(memory_usage);
+        }
+        limits
+    }
+
+    /// Memory the group may still allocate, if it has a limit.
+    #[must_use]
+    pub fn memory_available(&self) -> Option<u64> {
+        let limit = self.memory_limit?;
+        Some(limit.saturating_sub(self.memory_usage.unwrap_or(0)))
+    }
+}
+
+/// The group's directories for `controller`, deepest first.
+fn hierarchy(membership: &str, mount: &Path, controller: &str) -> Option<Vec<Hierarchy>> {
+    let mut unified = None;
+    for line in membership.lines() {
+        let mut fields = line.splitn(3, ':');
+        let (Some(id), Some(controllers), Some(path)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        if id == "0" && controllers.is_empty() {
+            unified = Some(path);
+        } else if controllers.split(',').any(|name| name == controller) {
+            let dirs = ancestors(&mount.join(controllers), path);
+            return Some(dirs.into_iter().map(Hierarchy::V1).collect());
+        }
+    }
+
+    // Hybrid systems mount the v2 hierarchy below the v1 ones.
+    let path = unified?;
+    let mount = if mount.join("cgroup.controllers").exists() {
+        mount.to_path_buf()
+    } else {
+        mount.join("unified")
+    };
+    Some(ancestors(&mount, path).into_iter().map(Hierarchy::V2).collect())
+}
+
+/// Existing directories from `mount/path` up to `mount`.
+fn ancestors(mount: &Path, path: &str) -> Vec<PathBuf> {
+    let mut dir = mount.join(path.trim_start_matches('/'));
+    let mut dirs = Vec::new();
+    loop {
+        if dir.is_dir() {
+            dirs.push(dir.clone());
+        }
+        if dir == mount || !dir.pop() {
+            break;
+        }
+    }
+    dirs
+}
+
+/// Smallest value of `read` over `groups`.
+fn tightest<T: PartialOrd>(
+    groups: &[Hierarchy], read: impl Fn(&Hierarchy) -> Option<T>,
+) -> Option<T> {
+    groups.iter().filter_map(read).reduce(|a, b| if b < a { b } else { a })
+}
+
+fn read(dir: &Path, name: &str) -> Option<String> {
+    Some(fs::read_to_string(dir.join(name)).ok()?.trim().to_owned())
+}
+
+fn cpu_quota(group: &Hierarchy) -> Option<f64> {
+    let (quota, period) = match group {
+        Hierarchy::V2(dir) => {
+            let max = read(dir, "cpu.max")?;
+            let (quota, period) = max.split_once(' ')?;
+            (quota.parse::<i64>().ok()?, period.trim().parse::<i64>().ok()?)
+        },
+        Hierarchy::V1(dir) => (
+            read(dir, "cpu.cfs_quota_us")?.parse().ok()?,
+            read(dir, "cpu.cfs_period_us")?.parse().ok()?,
+        ),
+    };
+    // v2 writes "max" and v1 writes -1 for no quota.
+    (quota > 0 && period > 0).then(|| quota as f64 / period as f64)
+}
+
+fn cpuset(group: &Hierarchy) -> Option<Vec<usize>> {
+    let list = match group {
+        Hierarchy::V2(dir) => read(dir, "cpuset.cpus.effective")?,
+        Hierarchy::V1(dir) => {
+            read(dir, "cpuset.effective_cpus").or_else(|| read(dir, "cpuset.cpus"))?
+        },
+    };
+    parse_cpu_list(&list)
+}
+
+fn memory_limit(group: &Hierarchy) -> Option<u64> {
+    let limit: u64 = match group {
+        Hierarchy::V2(dir) => read(dir, "memory.max")?.parse().ok()?,
+        Hierarchy::V1(dir) => read(dir, "memory.limit_in_bytes")?.parse().ok()?,
+    };
+    (limit < UNLIMITED_MEMORY).then_some(limit)
+}
+
+fn memory_usage(group: &Hierarchy) -> Option<u64> {
+    match group {
+        Hierarchy::V2(dir) => read(dir, "memory.current")?.parse().ok(),
+        Hierarchy::V1(dir) => read(dir, "memory.usage_in_bytes")?.parse().ok(),
+    }
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+    use crate::r#impl::test_tree::TempTree;
+
+    #[test]
+    fn test_v2_limits_take_the_tightest_ancestor() {
+        let root = TempTree::with_files(
+            "cgroup-v2",
+            &[
+                ("proc/self/cgroup", "0::/kubepods/pod1/miner\n"),
+                ("sys/fs/cgroup/cgroup.controllers", "cpuset cpu memory\n"),
+                ("sys/fs/cgroup/kubepods/cpu.max", "400000 100000\n"),
+                ("sys/fs/cgroup/kubepods/memory.max", "max\n"),
+                ("sys/fs/cgroup/kubepods/pod1/cpu.max", "150000 100000\n"),
+                ("sys/fs/cgroup/kubepods/pod1/memory.max", "2147483648\n"),
+                ("sys/fs/cgroup/kubepods/pod1/miner/cpu.max", "max 100000\n"),
+                ("sys/fs/cgroup/kubepods/pod1/miner/cpuset.cpus.effective", "2-3,6\n"),
+                ("sys/fs/cgroup/kubepods/pod1/miner/memory.max", "4294967296\n"),
+                ("sys/fs/cgroup/kubepods/pod1/miner/memory.current", "536870912\n"),
+            ],
+        );
+
+        let limits = CgroupLimits::from_root(root.path());
+        assert_eq!(limits.cpu_quota, Some(1.5));
+        assert_eq!(limits.cpuset, Some(vec![2, 3, 6]));
+        assert_eq!(limits.memory_limit, Some(2 << 30));
+        assert_eq!(limits.memory_available(), Some(3 << 29));
+    }
+
+    #[test]
+    fn test_v1_limits_at_the_mount_root() {
+        // Docker on cgroup v1: /proc shows the host path, but the
+        // container's own group is mounted at the root.
+        let root = TempTree::with_files(
+            "cgroup-v1",
+            &[
+                (
+                    "proc/self/cgroup",
+                    "12:memory:/docker/abc\n6:cpuset:/docker/abc\n4:cpu,cpuacct:/docker/abc\n\
+                 0::/system.slice/docker.service\n",
+                ),
+                ("sys/fs/cgroup/cpu,cpuacct/cpu.cfs_quota_us", "200000\n"),
+                ("sys/fs/cgroup/cpu,cpuacct/cpu.cfs_period_us", "100000\n"),
+                ("sys/fs/cgroup/cpuset/cpuset.cpus", "0-3\n"),
+                ("sys/fs/cgroup/memory/memory.limit_in_bytes", "1073741824\n"),
+                ("sys/fs/cgroup/memory/memory.usage_in_bytes", "268435456\n"),
+                ("sys/fs/cgroup/unified/cgroup.procs", "1\n"),
+            ],
+        );
+
+        let limits = CgroupLimits::from_root(root.path());
+        assert_eq!(limits.cpu_quota, Some(2.0));
+        assert_eq!(limits.cpuset, Some(vec![0, 1, 2, 3]));
+        assert_eq!(limits.memory_limit, Some(1 << 30));
+        assert_eq!(limits.memory_usage, Some(1 << 28));
+    }
+
+    #[test]
+    fn test_unlimited_and_missing() {
+        let root = TempTree::with_files(
+            "cgroup-unlimited",
+            &[
+                ("proc/self/cgroup", "4:memory:/\n3:cpu:/\n0::/\n"),
+                ("sys/fs/cgroup/cpu/cpu.cfs_quota_us", "-1\n"),
+                ("sys/fs/cgroup/cpu/cpu.cfs_period_us", "100000\n"),
+                ("sys/fs/cgroup/memory/memory.limit_in_bytes", "9223372036854771712\n"),
+            ],
+        );
+        assert_eq!(CgroupLimits::from_root(root.path()), CgroupLimits::default());
+        assert_eq!(CgroupLimits::from_root(&root.join("missing")), CgroupLimits::default());
+    }
+}
@@ -1,5 +1,7 @@
 //! Mining plugin configuration.
 
+use crate::{r#impl::LoadPolicy, types::CorePreference};
+
 /// Configuration for the mining plugin.
 #[derive(Debug, Clone)]
 pub struct MiningConfig {
@@ -7,6 +9,12 @@ pub struct MiningConfig {
     pub max_cpu_percentage:    u8,
     /// Run mining at background priority.
     pub background_priority:   bool,
+    /// Pin each mining thread to its own core.
+    pub pin_workers:           bool,
+    /// Cores mining threads never run on (logical CPU numbers).
+    pub excluded_cores:        Vec<usize>,
+    /// Kind of core to mine on when the CPU is hybrid.
+    pub core_preference:       CorePreference,
     /// Number of mining threads (0 = auto-detect).
     pub thread_count:          usize,
     /// Pool URL for stratum connection.
@@ -19,6 +27,8 @@ pub struct MiningConfig {
     pub min_hashrate:          f64,
     /// Auto-pause when system is busy.
     pub auto_pause_on_load:    bool,
+    /// Load thresholds for `auto_pause_on_load`.
+    pub load_policy:           LoadPolicy,
     /// CPU temperature threshold for throttling (Celsius).
     pub thermal_throttle_temp: Option<u8>,
 }
@@ -28,12 +38,16 @@ impl Default for MiningConfig {
         Self {
             max_cpu_percentage:    25, // Conservative default
             background_priority:   true,
+            pin_workers:           false,
+            excluded_cores:        Vec::new(),
+            core_preference:       CorePreference::Any,
             thread_count:          0, // Auto-detect
             pool_url:              None,
             worker_name:           String::from("essentia_worker"),
             gpu_enabled:           false,
             min_hashrate:          0.0,
             auto_pause_on_load:    true,
+            load_policy:           LoadPolicy::default(),
             thermal_throttle_temp: Some(80),
         }
     }
@@ -60,6 +74,29 @@ impl MiningConfig {
         self
     }
 
+    /// Pin each mining thread to its own core.
+    #[must_use]
+    pub fn with_core_pinning(mut self, enabled: bool) -> Self {
+        self.pin_workers = enabled;
+        self
+    }
+
+    /// Keep mining threads off `cores`, e.g. to leave one free for
+    /// interactive work.
+    #[must_use]
+    pub fn with_excluded_cores(mut self, cores: impl IntoIterator<Item = usize>) -> Self {
+        self.excluded_cores = cores.into_iter().collect();
+        self
+    }
+
+    /// Run mining threads on efficiency or performance cores of a hybrid
+    /// CPU.
+    #[must_use]
+    pub fn with_core_preference(mut self, preference: CorePreference) -> Self {
+        self.core_preference = preference;
+        self
+    }
+
     /// Set explicit thread count.
     #[must_use]
     pub fn with_thread_count(mut self, count: usize) -> Self {
@@ -88,15 +125,31 @@ impl MiningConfig {
         self
     }
 
+    /// Set the CPU temperature limit in Celsius; `None` disables thermal
+    /// throttling.
+    #[must_use]
+    pub fn with_thermal_limit(mut self, celsius: Option<u8>) -> Self {
+        self.thermal_throttle_temp = celsius;
+        self
+    }
+
+    /// Enable/disable pausing while the system is busy, with `policy`.
+    #[must_use]
+    pub fn with_load_policy(mut self, enabled: bool, policy: LoadPolicy) -> Self {
+        self.auto_pause_on_load = enabled;
+        self.load_policy = policy;
+        self
+    }
+
     /// Calculate effective thread count based on config and hardware.
     #[must_use]
     pub fn effective_thread_count(&self, available_cores: usize) -> usize {
         if self.thread_count > 0 {
             self.thread_count.min(available_cores)
         } else {
-            // Auto-detect: use percentage of available cores
-            let target =
-                (available_cores as f64 * (self.max_cpu_percentage as f64 / 100.0)) as usize;
+            // Auto-detect: enough threads for the percentage of available
+            // cores; each one's duty cycle makes up the fraction
+            let target = (available_cores * self.max_cpu_percentage as usize).div_ceil(100);
             target.max(1)
         }
     }
@@ -112,6 +165,13 @@ mod tests {
         assert_eq!(config.effective_thread_count(8), 4);
     }
 
+    #[test]
+    fn test_effective_thread_count_rounds_up() {
+        let config = MiningConfig::default().with_max_cpu_usage(30);
+        assert_eq!(config.effective_thread_count(4), 2);
+        assert_eq!(config.effective_thread_count(1), 1);
+    }
+
     #[test]
     fn test_effective_thread_count_explicit() {
         let config = MiningConfig::default().with_thread_count(2);
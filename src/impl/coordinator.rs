@@ -1,24 +1,192 @@
 //! Mining coordinator for managing mining threads.
+//!
+//! Every hash that meets the job target is sent as a [`FoundShare`] over a
+//! channel; [`MiningCoordinator::take_found_shares`] drains it so the caller
+//! can pass each share to [`PoolClientTrait::submit_share`].
+//!
+//! ## Work restarts
+//!
+//! [`MiningCoordinatorTrait::update_job`] swaps the job for all workers
+//! without stopping them. Each job gets a generation number. Workers check
+//! for a newer clean job before every batch and for any newer job every
+//! [`SWITCH_INTERVAL`] hashes. A clean job also discards shares of earlier
+//! jobs that have not been taken yet, since the pool would reject them as
+//! stale. A job that only changes the target, as `mining.set_difficulty`
+//! does, is taken over in place: workers keep their nonce and extranonce 2.
+//!
+//! Each worker owns a slice of the nonce space. When the slice is used up it
+//! rolls the version bits in the job's mask, then extranonce 2 and then
+//! ntime with [`roll_work`], so every worker keeps hashing headers no other
+//! worker sees; only when all three are exhausted does it wait for the next
+//! job.
+//!
+//! Each job is hashed with its [`PowAlgorithm`], or the coordinator's
+//! default when the job names none; workers scan one batch of `lanes()`
+//! nonces at a time. The default is [`Sha256d`] on a [`HashBackend`] picked
+//! from the CPU features in the hardware profile by [`select_hash_backend`].
+//!
+//! ## Threads
+//!
+//! Each worker runs on a dedicated OS thread named `mining-worker-<n>`.
+//! Workers check the running flag after every batch and idle workers are
+//! woken, so [`MiningCoordinatorTrait::stop`] only waits about one batch
+//! before joining them. Dropping the coordinator stops it the same way.
+//! Each thread first applies its [`WorkerPlacement`]: background priority
+//! and, if configured, core pinning, excluded cores or a preferred core
+//! class on hybrid CPUs. Every worker reports the result back to
+//! [`MiningCoordinatorTrait::start`], which stops them all and fails if any
+//! placement could not be applied.
+//!
+//! ## CPU limit
+//!
+//! `max_cpu_percentage` of the CPU allowance (the physical cores, narrowed
+//! by any cgroup quota or cpuset) is split evenly over the workers, and
+//! each runs on a duty cycle for its share, so 30% of four cores is two
+//! threads hashing 60% of the time. The limit can be changed while mining
+//! with [`MiningCoordinator::set_max_cpu_percentage`].
+//! [`MiningCoordinator::set_thermal_scale`] slows the duty cycle down further
+//! while the CPU runs hot, and [`MiningCoordinator::pause`] holds the workers
+//! at their next check without stopping the threads.
+//!
+//! [`PoolClientTrait::submit_share`]: crate::traits::PoolClientTrait::submit_share
 
-use std::sync::{
-    Arc,
-    atomic::{AtomicBool, AtomicU64, Ordering},
+use std::{
+    sync::{
+        Arc, Condvar, Mutex,
+        atomic::{AtomicBool, AtomicU8, AtomicU64, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
 use crate::{
     errors::{MiningError, MiningResult},
-    r#impl::{MiningConfig, MiningHardwareProfile, double_sha256},
-    traits::MiningCoordinatorTrait,
-    types::{BlockHeader, HashTarget, MiningJob, MiningStats},
+    r#impl::{
+        HashBackend, MiningConfig, MiningHardwareProfile, Sha256d, WorkerPlacement, roll_work,
+        select_hash_backend,
+        throttle::{DutyWindow, Throttle, duty_cycle},
+    },
+    traits::{MiningCoordinatorTrait, PowAlgorithm},
+    types::{FoundShare, MiningJob, MiningStats, PauseReason},
 };
 
+/// Hashes between checks for a new job without `clean_jobs`.
+const SWITCH_INTERVAL: u64 = 1000;
+
+/// How often idle workers recheck whether mining was stopped.
+const IDLE_POLL: Duration = Duration::from_millis(100);
+
 /// Mining coordinator that manages background mining threads.
 pub struct MiningCoordinator {
     config:       MiningConfig,
     hardware:     MiningHardwareProfile,
+    backend:      Arc<dyn HashBackend>,
+    algorithm:    Arc<dyn PowAlgorithm>,
+    running:      Arc<AtomicBool>,
+    total_hashes: Arc<AtomicU64>,
+    shares_found: Arc<AtomicU64>,
+    work:         Arc<WorkSlot>,
+    throttle:     Arc<Throttle>,
+    cpu_limit:    AtomicU8,
+    share_sender: Sender<(u64, FoundShare)>,
+    found_shares: Mutex<Receiver<(u64, FoundShare)>>,
+    threads:      Mutex<Vec<JoinHandle<()>>>,
+}
+
+/// State shared between the coordinator and one mining thread.
+#[derive(Clone)]
+struct Worker {
+    algorithm:    Arc<dyn PowAlgorithm>,
     running:      Arc<AtomicBool>,
     total_hashes: Arc<AtomicU64>,
     shares_found: Arc<AtomicU64>,
+    work:         Arc<WorkSlot>,
+    throttle:     Arc<Throttle>,
+    share_sender: Sender<(u64, FoundShare)>,
+}
+
+impl Worker {
+    /// Algorithm `job` is hashed with.
+    fn algorithm<'a>(&'a self, job: &'a MiningJob) -> &'a dyn PowAlgorithm {
+        job.algorithm.as_deref().unwrap_or(&*self.algorithm)
+    }
+}
+
+/// Job published to the workers.
+struct ActiveJob {
+    job:        MiningJob,
+    generation: u64,
+    published:  Instant,
+}
+
+/// The current job and its generation counters.
+#[derive(Default)]
+struct WorkSlot {
+    job:              Mutex<Option<Arc<ActiveJob>>>,
+    changed:          Condvar,
+    generation:       AtomicU64,
+    clean_generation: AtomicU64,
+    restart_latency:  Mutex<Option<Duration>>,
+}
+
+impl WorkSlot {
+    /// Make `job` the current job and wake idle workers.
+    fn publish(&self, job: MiningJob) {
+        let Ok(mut current) = self.job.lock() else {
+            return;
+        };
+        let generation = self.generation.load(Ordering::Acquire) + 1;
+        let clean = job.clean_jobs;
+        *current = Some(Arc::new(ActiveJob { job, generation, published: Instant::now() }));
+        if let Ok(mut latency) = self.restart_latency.lock() {
+            *latency = None;
+        }
+        if clean {
+            self.clean_generation.store(generation, Ordering::Release);
+        }
+        self.generation.store(generation, Ordering::Release);
+        self.changed.notify_all();
+    }
+
+    fn current(&self) -> Option<Arc<ActiveJob>> {
+        self.job.lock().ok().and_then(|current| current.clone())
+    }
+
+    /// Whether a job newer than `generation` must be picked up now.
+    ///
+    /// Clean jobs always count; other jobs only `at_interval`.
+    fn has_newer(&self, generation: u64, at_interval: bool) -> bool {
+        let latest = if at_interval { &self.generation } else { &self.clean_generation };
+        latest.load(Ordering::Acquire) > generation
+    }
+
+    /// Block until a job newer than `generation` is published or `running`
+    /// is cleared.
+    fn wait_for_newer(&self, generation: u64, running: &AtomicBool) -> Option<Arc<ActiveJob>> {
+        let mut current = self.job.lock().ok()?;
+        loop {
+            if !running.load(Ordering::Relaxed) {
+                return None;
+            }
+            if let Some(ref job) = *current
+                && job.generation > generation
+            {
+                return Some(Arc::clone(job));
+            }
+            current = self.changed.wait_timeout(current, IDLE_POLL).ok()?.0;
+        }
+    }
+
+    /// Record how long a worker took to pick up `job`.
+    fn record_restart(&self, job: &ActiveJob) {
+        let elapsed = job.published.elapsed();
+        if let Ok(mut latency) = self.restart_latency.lock()
+            && self.generation.load(Ordering::Acquire) == job.generation
+        {
+            *latency = Some(latency.map_or(elapsed, |latency| latency.max(elapsed)));
+        }
+    }
 }
 
 impl MiningCoordinator {
@@ -28,9 +196,19 @@ impl MiningCoordinator {
     ///
     /// Returns `MiningError::HardwareDetection` if hardware detection fails.
     pub fn new(config: MiningConfig) -> MiningResult<Self> {
-        use crate::traits::MiningHardwareTrait;
+        Self::with_hardware(config, MiningHardwareProfile::detect()?)
+    }
 
-        let hardware = MiningHardwareProfile::detect()?;
+    /// Create a coordinator for an already detected `hardware` profile.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MiningError::HardwareDetection` if the hardware does not
+    /// meet the minimum requirements for mining.
+    pub fn with_hardware(
+        config: MiningConfig, hardware: MiningHardwareProfile,
+    ) -> MiningResult<Self> {
+        use crate::traits::MiningHardwareTrait;
 
         if !hardware.is_suitable_for_mining() {
             return Err(MiningError::HardwareDetection(
@@ -38,47 +216,242 @@ impl MiningCoordinator {
             ));
         }
 
+        let (share_sender, found_shares) = mpsc::channel();
+        let backend = select_hash_backend(&hardware);
+
         Ok(Self {
+            cpu_limit: AtomicU8::new(config.max_cpu_percentage),
             config,
             hardware,
+            algorithm: Arc::new(Sha256d::new(Arc::clone(&backend))),
+            backend,
             running: Arc::new(AtomicBool::new(false)),
             total_hashes: Arc::new(AtomicU64::new(0)),
             shares_found: Arc::new(AtomicU64::new(0)),
+            work: Arc::new(WorkSlot::default()),
+            throttle: Arc::new(Throttle::new(1.0)),
+            share_sender,
+            found_shares: Mutex::new(found_shares),
+            threads: Mutex::new(Vec::new()),
         })
     }
 
+    /// Use `backend` instead of the one selected from the hardware profile.
+    ///
+    /// This also makes SHA-256d on `backend` the default algorithm.
+    #[must_use]
+    pub fn with_hash_backend(mut self, backend: Arc<dyn HashBackend>) -> Self {
+        self.algorithm = Arc::new(Sha256d::new(Arc::clone(&backend)));
+        self.backend = backend;
+        self
+    }
+
+    /// Hash jobs that do not name an algorithm with `algorithm` instead of
+    /// SHA-256d.
+    #[must_use]
+    pub fn with_pow_algorithm(mut self, algorithm: Arc<dyn PowAlgorithm>) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Name of the SHA-256d hashing backend the workers use.
+    #[must_use]
+    pub fn hash_backend(&self) -> &'static str {
+        self.backend.name()
+    }
+
+    /// Name of the algorithm used for jobs that do not name one.
+    #[must_use]
+    pub fn pow_algorithm(&self) -> &'static str {
+        self.algorithm.name()
+    }
+
+    /// Take the shares found since the last call, oldest first.
+    ///
+    /// Shares of jobs replaced by a `clean_jobs` job are left out.
+    #[must_use]
+    pub fn take_found_shares(&self) -> Vec<FoundShare> {
+        let clean_generation = self.work.clean_generation.load(Ordering::Acquire);
+        self.found_shares
+            .lock()
+            .map(|shares| {
+                shares
+                    .try_iter()
+                    .filter(|(generation, _)| *generation >= clean_generation)
+                    .map(|(_, share)| share)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Job the workers are currently mining.
+    #[must_use]
+    pub fn current_job(&self) -> Option<MiningJob> {
+        self.work.current().map(|active| active.job.clone())
+    }
+
+    fn worker(&self) -> Worker {
+        Worker {
+            algorithm:    Arc::clone(&self.algorithm),
+            running:      Arc::clone(&self.running),
+            total_hashes: Arc::clone(&self.total_hashes),
+            shares_found: Arc::clone(&self.shares_found),
+            work:         Arc::clone(&self.work),
+            throttle:     Arc::clone(&self.throttle),
+            share_sender: self.share_sender.clone(),
+        }
+    }
+
+    /// Change the CPU limit, taking effect on running workers right away.
+    ///
+    /// Only the duty cycle changes; the number of threads stays as started.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MiningError::Configuration` if `percentage` is not 1-100.
+    pub fn set_max_cpu_percentage(&self, percentage: u8) -> MiningResult<()> {
+        if percentage == 0 || percentage > 100 {
+            return Err(MiningError::Configuration(
+                "CPU percentage must be between 1 and 100".into(),
+            ));
+        }
+        self.cpu_limit.store(percentage, Ordering::SeqCst);
+        let threads = self.worker_count();
+        if threads > 0 {
+            self.throttle.set_duty(duty_cycle(percentage, self.hardware.cpu_allowance(), threads));
+        }
+        Ok(())
+    }
+
+    /// Scale every worker's duty cycle by `scale` (`0.0..=1.0`) to shed heat.
+    pub fn set_thermal_scale(&self, scale: f64) {
+        self.throttle.set_scale(scale);
+    }
+
+    /// Hold all workers until [`Self::resume`] is called for every reason
+    /// given here.
+    pub fn pause(&self, reason: PauseReason) {
+        self.throttle.pause(reason);
+    }
+
+    /// Clear a pause; workers continue once no reason is left.
+    pub fn resume(&self, reason: PauseReason) {
+        self.throttle.resume(reason);
+    }
+
+    /// Whether the workers are paused for any reason.
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.throttle.is_paused()
+    }
+
+    /// Whether `reason` is holding the workers.
+    #[must_use]
+    pub fn is_paused_for(&self, reason: PauseReason) -> bool {
+        self.throttle.is_paused_for(reason)
+    }
+
+    /// Number of mining threads started.
+    #[must_use]
+    pub fn worker_count(&self) -> usize {
+        self.threads.lock().map(|threads| threads.len()).unwrap_or_default()
+    }
+
+    /// Fraction of the time each worker spends hashing.
+    #[must_use]
+    pub fn duty_cycle(&self) -> f64 {
+        self.throttle.duty()
+    }
+
     /// Mining thread function.
-    fn mining_thread(
-        running: Arc<AtomicBool>, total_hashes: Arc<AtomicU64>, shares_found: Arc<AtomicU64>,
-        mut header: BlockHeader, target: HashTarget, start_nonce: u32, end_nonce: u32,
-    ) {
+    ///
+    /// Hashes nonces `start_nonce..=end_nonce`, rolling extranonce 2 and
+    /// ntime whenever the range is used up.
+    fn mining_thread(worker: &Worker, start_nonce: u32, end_nonce: u32) {
+        let Some(mut active) = worker.work.current() else {
+            return;
+        };
+        let mut header = active.job.header.clone();
+        let mut search = worker.algorithm(&active.job).search(&header);
+        let mut extranonce2 = active.job.extranonce2.clone();
         let mut nonce = start_nonce;
+        let mut exhausted = false;
         let mut batch_count = 0u64;
+        let mut window = DutyWindow::start();
 
-        while running.load(Ordering::Relaxed) && nonce < end_nonce {
-            header.nonce = nonce;
-            let serialized = header.serialize();
-            let hash = double_sha256(&serialized);
-
-            // Check if hash meets target
-            if target.is_valid_hash(&hash) {
-                shares_found.fetch_add(1, Ordering::Relaxed);
+        while worker.running.load(Ordering::Relaxed) {
+            let next = if exhausted {
+                // Nothing left to roll: report progress and idle until new work.
+                worker.total_hashes.fetch_add(batch_count, Ordering::Relaxed);
+                batch_count = 0;
+                worker.work.wait_for_newer(active.generation, &worker.running)
+            } else if worker.work.has_newer(active.generation, batch_count == 0) {
+                worker.work.current()
+            } else {
+                None
+            };
+            if let Some(next) = next {
+                worker.work.record_restart(&next);
+                let retarget = is_retarget(&active.job, &next.job);
+                active = next;
+                if retarget {
+                    continue;
+                }
+                header = active.job.header.clone();
+                search = worker.algorithm(&active.job).search(&header);
+                extranonce2.clone_from(&active.job.extranonce2);
+                nonce = start_nonce;
+                if exhausted {
+                    // Waiting for work is not hashing time to make up for.
+                    window = DutyWindow::start();
+                    exhausted = false;
+                }
+                continue;
+            }
+            if exhausted {
+                break;
             }
 
-            batch_count += 1;
+            // Hash the next batch, never past the end of the range
+            let remaining = end_nonce.saturating_sub(nonce);
+            let count = search.lanes().min((remaining as usize).saturating_add(1));
+            search.scan(nonce, count, &active.job.target, &mut |found, hash| {
+                worker.shares_found.fetch_add(1, Ordering::Relaxed);
+                // The receiver lives as long as the coordinator.
+                let _ = worker.share_sender.send((
+                    active.generation,
+                    FoundShare {
+                        job_id: active.job.job_id.clone(),
+                        extranonce2: extranonce2.clone(),
+                        ntime: header.timestamp,
+                        nonce: found,
+                        version: header.version,
+                        hash,
+                    },
+                ));
+            });
+
+            batch_count += count as u64;
 
             // Update total hashes periodically
-            if batch_count >= 1000 {
-                total_hashes.fetch_add(batch_count, Ordering::Relaxed);
+            if batch_count >= SWITCH_INTERVAL {
+                worker.total_hashes.fetch_add(batch_count, Ordering::Relaxed);
                 batch_count = 0;
+                worker.throttle.pace(&mut window, &worker.running);
             }
 
-            nonce = nonce.saturating_add(1);
+            if count > remaining as usize {
+                exhausted = !roll_work(&active.job, &mut extranonce2, &mut header);
+                search.set_header(&header);
+                nonce = start_nonce;
+            } else {
+                nonce += count as u32;
+            }
         }
 
         // Final update
         if batch_count > 0 {
-            total_hashes.fetch_add(batch_count, Ordering::Relaxed);
+            worker.total_hashes.fetch_add(batch_count, Ordering::Relaxed);
         }
     }
 }
@@ -89,46 +462,97 @@ impl MiningCoordinatorTrait for MiningCoordinator {
             return Err(MiningError::Coordinator("Mining already running".into()));
         }
 
-        self.running.store(true, Ordering::SeqCst);
-
-        let thread_count = self.config.effective_thread_count(self.hardware.physical_cores);
+        let thread_count = self.config.effective_thread_count(self.hardware.available_cores());
+        let placements = WorkerPlacement::plan(&self.config, &self.hardware, thread_count)?;
+        let thread_count = placements.len();
+        let percentage = self.cpu_limit.load(Ordering::SeqCst);
+        self.throttle.set_duty(duty_cycle(percentage, self.hardware.cpu_allowance(), thread_count));
         let nonce_range = u32::MAX / thread_count as u32;
+        let mut threads = Vec::with_capacity(thread_count);
+        let (placed_sender, placed) = mpsc::channel();
 
-        for thread_id in 0..thread_count {
+        self.running.store(true, Ordering::SeqCst);
+        self.work.publish(job);
+
+        for (thread_id, placement) in placements.into_iter().enumerate() {
             let start_nonce = thread_id as u32 * nonce_range;
+            // Ranges are inclusive; the last one absorbs the remainder.
             let end_nonce = if thread_id == thread_count - 1 {
                 u32::MAX
             } else {
                 start_nonce + nonce_range - 1
             };
 
-            let running = Arc::clone(&self.running);
-            let total_hashes = Arc::clone(&self.total_hashes);
-            let shares_found = Arc::clone(&self.shares_found);
-            let header = job.header.clone();
-            let target = job.target.clone();
+            let worker = self.worker();
+            let placed_sender = placed_sender.clone();
 
             // SSOP-EXEMPT(std::thread): Mining uses CPU-intensive threads for SHA256 hashing;
             // async runtime not suitable for compute-bound work
-            #[allow(clippy::let_underscore_future)]
-            let _ = essentia_async_runtime::spawn(async move {
-                Self::mining_thread(
-                    running,
-                    total_hashes,
-                    shares_found,
-                    header,
-                    target,
-                    start_nonce,
-                    end_nonce,
-                );
-            });
+            let spawned = thread::Builder::new().name(format!("mining-worker-{thread_id}")).spawn(
+                move || {
+                    let applied = placement.apply();
+                    let mine = applied.is_ok();
+                    // The receiver lives until `start` has heard every worker.
+                    let _ = placed_sender.send(applied);
+                    if mine {
+                        Self::mining_thread(&worker, start_nonce, end_nonce);
+                    }
+                },
+            );
+            match spawned {
+                Ok(handle) => threads.push(handle),
+                Err(e) => {
+                    self.running.store(false, Ordering::SeqCst);
+                    self.work.changed.notify_all();
+                    join_all(threads);
+                    return Err(MiningError::ResourceAllocation(format!(
+                        "Failed to spawn mining thread: {e}"
+                    )));
+                },
+            }
         }
 
+        // Only the workers hold senders now, so a worker that dies before
+        // replying ends the wait instead of hanging it.
+        drop(placed_sender);
+        let replies: Vec<_> = placed.iter().take(threads.len()).collect();
+        let exited = replies.len() < threads.len();
+        let failure = replies.into_iter().find_map(Result::err).or_else(|| {
+            exited.then(|| {
+                MiningError::ResourceAllocation(
+                    "Mining thread exited before applying its placement".into(),
+                )
+            })
+        });
+        if let Some(e) = failure {
+            self.running.store(false, Ordering::SeqCst);
+            self.work.changed.notify_all();
+            self.throttle.wake();
+            join_all(threads);
+            return Err(e);
+        }
+
+        if let Ok(mut running) = self.threads.lock() {
+            running.extend(threads);
+        }
+        Ok(())
+    }
+
+    fn update_job(&self, job: MiningJob) -> MiningResult<()> {
+        if !self.running.load(Ordering::SeqCst) {
+            return Err(MiningError::Coordinator("Mining not running".into()));
+        }
+        self.work.publish(job);
+        self.throttle.wake();
         Ok(())
     }
 
     fn stop(&self) {
         self.running.store(false, Ordering::SeqCst);
+        self.work.changed.notify_all();
+        self.throttle.wake();
+        let threads = self.threads.lock().map(|mut threads| std::mem::take(&mut *threads));
+        join_all(threads.unwrap_or_default());
     }
 
     fn is_running(&self) -> bool {
@@ -139,35 +563,514 @@ impl MiningCoordinatorTrait for MiningCoordinator {
         MiningStats {
             total_hashes: self.total_hashes.load(Ordering::Relaxed),
             shares_found: self.shares_found.load(Ordering::Relaxed),
+            restart_latency: self.work.restart_latency.lock().ok().and_then(|latency| *latency),
             ..Default::default()
         }
     }
 }
 
+impl Drop for MiningCoordinator {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Whether `next` hashes the same headers as `current` and differs at most
+/// in its target.
+fn is_retarget(current: &MiningJob, next: &MiningJob) -> bool {
+    let same_algorithm = match (&current.algorithm, &next.algorithm) {
+        (Some(current), Some(next)) => Arc::ptr_eq(current, next),
+        (current, next) => current.is_none() && next.is_none(),
+    };
+    current.job_id == next.job_id
+        && current.header == next.header
+        && current.extranonce1 == next.extranonce1
+        && current.extranonce2 == next.extranonce2
+        && current.coinbase == next.coinbase
+        && current.version_mask == next.version_mask
+        && current.max_ntime == next.max_ntime
+        && same_algorithm
+}
+
+/// Wait for every thread in `threads` to exit.
+fn join_all(threads: Vec<JoinHandle<()>>) {
+    for handle in threads {
+        // A panicked worker has exited too; its hashes are simply lost.
+        let _ = handle.join();
+    }
+}
+
 #[cfg(all(test, feature = "full-tests"))]
 mod tests {
+    use core::ops::RangeInclusive;
+
     use super::*;
+    use crate::{
+        r#impl::{CgroupLimits, JobTemplate, double_sha256},
+        types::{BlockHeader, HashTarget},
+    };
+
+    fn mining_coordinator(config: MiningConfig) -> MiningResult<MiningCoordinator> {
+        MiningCoordinator::with_hardware(config, MiningHardwareProfile::test_machine())
+    }
 
     #[test]
     fn test_coordinator_creation() {
         let config = MiningConfig::default();
-        let coordinator = MiningCoordinator::new(config);
+        let coordinator = mining_coordinator(config);
         assert!(coordinator.is_ok());
+
+        let single_core =
+            MiningHardwareProfile { physical_cores: 1, ..MiningHardwareProfile::test_machine() };
+        assert!(matches!(
+            MiningCoordinator::with_hardware(MiningConfig::default(), single_core),
+            Err(MiningError::HardwareDetection(_))
+        ));
     }
 
     #[test]
     fn test_coordinator_not_running_initially() {
         let config = MiningConfig::default();
-        let coordinator = MiningCoordinator::new(config).expect("test assertion");
+        let coordinator = mining_coordinator(config).expect("test assertion");
         assert!(!coordinator.is_running());
     }
 
     #[test]
     fn test_stats_initial() {
         let config = MiningConfig::default();
-        let coordinator = MiningCoordinator::new(config).expect("test assertion");
+        let coordinator = mining_coordinator(config).expect("test assertion");
         let stats = coordinator.stats();
         assert_eq!(stats.total_hashes, 0);
         assert_eq!(stats.shares_found, 0);
     }
+
+    /// Bitcoin genesis block header.
+    fn genesis_job() -> MiningJob {
+        let mut merkle_root = crate::r#impl::decode_hex(
+            "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b",
+        )
+        .expect("test assertion");
+        merkle_root.reverse();
+
+        MiningJob {
+            job_id:           "genesis".into(),
+            header:           BlockHeader {
+                version:         1,
+                prev_block_hash: [0; 32],
+                merkle_root:     merkle_root.try_into().expect("test assertion"),
+                timestamp:       1_231_006_505,
+                bits:            0x1d00_ffff,
+                nonce:           0,
+            },
+            target:           HashTarget::from_bits(0x1d00_ffff),
+            extranonce1:      Vec::new(),
+            extranonce2_size: 4,
+            extranonce2:      vec![0, 0, 0, 7],
+            clean_jobs:       true,
+            version_mask:     0,
+            coinbase:         None,
+            max_ntime:        1_231_006_505,
+            algorithm:        None,
+        }
+    }
+
+    /// Run one worker over `nonces` on a plain thread, as `start` would.
+    fn spawn_worker(
+        coordinator: &MiningCoordinator, job: MiningJob, nonces: RangeInclusive<u32>,
+    ) -> std::thread::JoinHandle<()> {
+        coordinator.running.store(true, Ordering::SeqCst);
+        coordinator.work.publish(job);
+        let worker = coordinator.worker();
+        std::thread::spawn(move || {
+            MiningCoordinator::mining_thread(&worker, *nonces.start(), *nonces.end());
+        })
+    }
+
+    fn wait_for_shares(coordinator: &MiningCoordinator, count: u64) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while coordinator.stats().shares_found < count {
+            assert!(Instant::now() < deadline, "no share found in time");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    const GENESIS_NONCES: RangeInclusive<u32> = 2_083_236_890..=2_083_236_899;
+
+    #[test]
+    fn test_found_share_is_reported() {
+        let coordinator = mining_coordinator(MiningConfig::default()).expect("test assertion");
+        let worker = spawn_worker(&coordinator, genesis_job(), GENESIS_NONCES);
+        wait_for_shares(&coordinator, 1);
+        coordinator.stop();
+        worker.join().expect("test assertion");
+
+        let shares = coordinator.take_found_shares();
+        assert_eq!(shares.len(), 1);
+        let share = &shares[0];
+        assert_eq!(share.job_id, "genesis");
+        assert_eq!(share.extranonce2, [0, 0, 0, 7]);
+        assert_eq!(share.ntime, 1_231_006_505);
+        assert_eq!(share.nonce, 2_083_236_893);
+        assert_eq!(share.version, 1);
+        assert_eq!(
+            crate::r#impl::encode_hex(&share.hash),
+            "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"
+        );
+
+        let stats = coordinator.stats();
+        assert_eq!(stats.total_hashes, 10);
+        assert_eq!(stats.shares_found, 1);
+        assert!(coordinator.take_found_shares().is_empty());
+    }
+
+    /// Single SHA-256 of the header, hashed one nonce at a time.
+    struct SingleSha256;
+
+    struct SingleSha256Search(BlockHeader);
+
+    impl PowAlgorithm for SingleSha256 {
+        fn name(&self) -> &'static str {
+            "sha256"
+        }
+
+        fn hash(&self, data: &[u8]) -> [u8; 32] {
+            crate::r#impl::sha256(data)
+        }
+
+        fn search(&self, header: &BlockHeader) -> Box<dyn crate::traits::NonceSearch> {
+            Box::new(SingleSha256Search(header.clone()))
+        }
+    }
+
+    impl crate::traits::NonceSearch for SingleSha256Search {
+        fn lanes(&self) -> usize {
+            1
+        }
+
+        fn set_header(&mut self, header: &BlockHeader) {
+            self.0 = header.clone();
+        }
+
+        fn scan(
+            &mut self, first_nonce: u32, count: usize, target: &HashTarget,
+            found: &mut dyn FnMut(u32, [u8; 32]),
+        ) {
+            if count > 0 {
+                self.0.nonce = first_nonce;
+                let digest = SingleSha256.hash(&SingleSha256.serialize_header(&self.0));
+                if let Some(hash) = SingleSha256.check_target(&digest, target) {
+                    found(first_nonce, hash);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_job_selects_its_algorithm() {
+        let coordinator = mining_coordinator(MiningConfig::default()).expect("test assertion");
+        assert_eq!(coordinator.pow_algorithm(), "sha256d");
+
+        let job = MiningJob {
+            target: HashTarget { target: [0xFF; 32] },
+            algorithm: Some(Arc::new(SingleSha256)),
+            ..genesis_job()
+        };
+        let worker = spawn_worker(&coordinator, job.clone(), 0..=3);
+        wait_for_shares(&coordinator, 4);
+        coordinator.stop();
+        worker.join().expect("test assertion");
+
+        let shares = coordinator.take_found_shares();
+        assert_eq!(shares.iter().map(|share| share.nonce).collect::<Vec<_>>(), [0, 1, 2, 3]);
+        for share in shares {
+            let mut header = job.header.clone();
+            header.nonce = share.nonce;
+            let mut hash = crate::r#impl::sha256(&header.serialize());
+            hash.reverse();
+            assert_eq!(share.hash, hash);
+        }
+    }
+
+    #[test]
+    fn test_scrypt_default_mines_litecoin_genesis() {
+        let coordinator = mining_coordinator(MiningConfig::default())
+            .expect("test assertion")
+            .with_pow_algorithm(Arc::new(crate::r#impl::Scrypt));
+        assert_eq!(coordinator.pow_algorithm(), "scrypt");
+
+        let mut merkle_root = crate::r#impl::decode_hex(
+            "97ddfbbae6be97fd6cdf3e7ca13232a3afff2353e29badfab7f73011edd4ced9",
+        )
+        .expect("test assertion");
+        merkle_root.reverse();
+        let mut job = genesis_job();
+        job.header.merkle_root = merkle_root.try_into().expect("test assertion");
+        job.header.timestamp = 1_317_972_665;
+        job.header.bits = 0x1e0f_fff0;
+        job.target = HashTarget::from_bits(job.header.bits);
+
+        let worker = spawn_worker(&coordinator, job, 2_084_524_491..=2_084_524_495);
+        wait_for_shares(&coordinator, 1);
+        coordinator.stop();
+        worker.join().expect("test assertion");
+
+        let shares = coordinator.take_found_shares();
+        assert_eq!(shares.len(), 1);
+        assert_eq!(shares[0].nonce, 2_084_524_493);
+        assert_eq!(
+            crate::r#impl::encode_hex(&shares[0].hash),
+            "0000050c34a64b415b6b15b37f2216634b5b1669cb9a2e38d76f7213b0671e00"
+        );
+    }
+
+    #[test]
+    fn test_start_runs_named_threads_and_stop_joins_them() {
+        let coordinator = mining_coordinator(MiningConfig::default()).expect("test assertion");
+        coordinator.start(genesis_job()).expect("test assertion");
+        let names: Vec<String> = coordinator
+            .threads
+            .lock()
+            .expect("test assertion")
+            .iter()
+            .filter_map(|handle| handle.thread().name().map(String::from))
+            .collect();
+        let expected =
+            coordinator.config.effective_thread_count(coordinator.hardware.available_cores());
+        assert_eq!(names.len(), expected);
+        assert_eq!(names[0], "mining-worker-0");
+
+        let started = Instant::now();
+        coordinator.stop();
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert!(!coordinator.is_running());
+        assert!(coordinator.threads.lock().expect("test assertion").is_empty());
+
+        // Every worker has exited, so nothing else holds the counters.
+        assert_eq!(Arc::strong_count(&coordinator.total_hashes), 1);
+        assert!(coordinator.start(genesis_job()).is_ok());
+    }
+
+    #[test]
+    fn test_max_cpu_percentage_sets_duty_cycle() {
+        let config = MiningConfig::default().with_max_cpu_usage(30).with_thread_count(2);
+        let coordinator = mining_coordinator(config).expect("test assertion");
+        let cores = coordinator.hardware.cpu_allowance();
+        coordinator.start(genesis_job()).expect("test assertion");
+        let threads = coordinator.threads.lock().expect("test assertion").len();
+        assert!((coordinator.duty_cycle() - duty_cycle(30, cores, threads)).abs() < 1e-9);
+
+        coordinator.set_max_cpu_percentage(10).expect("test assertion");
+        assert!((coordinator.duty_cycle() - duty_cycle(10, cores, threads)).abs() < 1e-9);
+        assert!(coordinator.duty_cycle() < 1.0);
+        assert!(matches!(
+            coordinator.set_max_cpu_percentage(0),
+            Err(MiningError::Configuration(_))
+        ));
+
+        // Workers deep in an idle period still stop promptly.
+        let started = Instant::now();
+        coordinator.stop();
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_pause_holds_hash_count() {
+        let coordinator = mining_coordinator(MiningConfig::default()).expect("test assertion");
+        coordinator.start(genesis_job()).expect("test assertion");
+        coordinator.pause(PauseReason::SystemLoad);
+        assert!(coordinator.is_paused());
+        assert!(coordinator.is_running());
+
+        // Workers stop at their next check, after at most one interval.
+        std::thread::sleep(Duration::from_millis(100));
+        let paused_at = coordinator.stats().total_hashes;
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(coordinator.stats().total_hashes, paused_at);
+
+        coordinator.resume(PauseReason::SystemLoad);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while coordinator.stats().total_hashes == paused_at {
+            assert!(Instant::now() < deadline, "workers did not resume");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+
+        coordinator.pause(PauseReason::SystemLoad);
+        let started = Instant::now();
+        coordinator.stop();
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_start_rejects_excluding_every_core() {
+        let coordinator = mining_coordinator(MiningConfig::default()).expect("test assertion");
+        let cores = coordinator.hardware.mining_cores(&[]);
+        let config = MiningConfig::default().with_excluded_cores(cores);
+        let coordinator = mining_coordinator(config).expect("test assertion");
+        assert!(matches!(coordinator.start(genesis_job()), Err(MiningError::Configuration(_))));
+        assert!(!coordinator.is_running());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_start_fails_when_placement_fails() {
+        // CPUs past the affinity mask cannot be pinned to.
+        let hardware = MiningHardwareProfile {
+            logical_cores: 2048,
+            cgroup: CgroupLimits { cpuset: Some(vec![1500, 1501]), ..CgroupLimits::default() },
+            ..MiningHardwareProfile::test_machine()
+        };
+        let config = MiningConfig::default().with_core_pinning(true);
+        let coordinator =
+            MiningCoordinator::with_hardware(config, hardware).expect("test assertion");
+        assert!(matches!(
+            coordinator.start(genesis_job()),
+            Err(MiningError::ResourceAllocation(_))
+        ));
+        assert!(!coordinator.is_running());
+        assert_eq!(coordinator.worker_count(), 0);
+    }
+
+    #[test]
+    fn test_drop_joins_workers() {
+        let coordinator = mining_coordinator(MiningConfig::default()).expect("test assertion");
+        coordinator.start(genesis_job()).expect("test assertion");
+        let total_hashes = Arc::clone(&coordinator.total_hashes);
+        assert!(Arc::strong_count(&total_hashes) > 2);
+        drop(coordinator);
+        assert_eq!(Arc::strong_count(&total_hashes), 1);
+    }
+
+    #[test]
+    fn test_update_job_requires_running() {
+        let coordinator = mining_coordinator(MiningConfig::default()).expect("test assertion");
+        assert!(matches!(coordinator.update_job(genesis_job()), Err(MiningError::Coordinator(_))));
+    }
+
+    #[test]
+    fn test_update_job_switches_live_workers() {
+        let coordinator = mining_coordinator(MiningConfig::default()).expect("test assertion");
+        let first = MiningJob { clean_jobs: false, ..genesis_job() };
+        let worker = spawn_worker(&coordinator, first, GENESIS_NONCES);
+        wait_for_shares(&coordinator, 1);
+        assert_eq!(coordinator.stats().restart_latency, None);
+
+        let second = MiningJob { job_id: "second".into(), clean_jobs: false, ..genesis_job() };
+        coordinator.update_job(second).expect("test assertion");
+        wait_for_shares(&coordinator, 2);
+        assert!(coordinator.stats().restart_latency.is_some());
+        assert_eq!(coordinator.current_job().map(|job| job.job_id).as_deref(), Some("second"));
+
+        // A clean job makes the untaken shares of earlier jobs stale.
+        let third = MiningJob { job_id: "third".into(), ..genesis_job() };
+        coordinator.update_job(third).expect("test assertion");
+        wait_for_shares(&coordinator, 3);
+        coordinator.stop();
+        worker.join().expect("test assertion");
+
+        let shares = coordinator.take_found_shares();
+        let job_ids: Vec<&str> = shares.iter().map(|share| share.job_id.as_str()).collect();
+        assert_eq!(job_ids, ["third"]);
+        assert_eq!(coordinator.stats().total_hashes, 30);
+    }
+
+    #[test]
+    fn test_new_target_keeps_nonce_position() {
+        let coordinator = mining_coordinator(MiningConfig::default()).expect("test assertion");
+        let hard = MiningJob { target: HashTarget { target: [0; 32] }, ..genesis_job() };
+        let worker = spawn_worker(&coordinator, hard.clone(), 0..=u32::MAX);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while coordinator.stats().total_hashes == 0 {
+            assert!(Instant::now() < deadline, "worker never reported hashes");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        let hashed = coordinator.stats().total_hashes;
+
+        let easy = MiningJob { target: HashTarget { target: [0xFF; 32] }, ..hard };
+        coordinator.update_job(easy).expect("test assertion");
+        wait_for_shares(&coordinator, 1);
+        coordinator.stop();
+        worker.join().expect("test assertion");
+
+        let share = &coordinator.take_found_shares()[0];
+        assert!(u64::from(share.nonce) >= hashed, "nonce restarted at {}", share.nonce);
+        assert_eq!(share.extranonce2, genesis_job().extranonce2);
+        assert_eq!(share.ntime, genesis_job().header.timestamp);
+    }
+
+    #[test]
+    fn test_exhausted_range_rolls_version_inside_mask() {
+        let coordinator = mining_coordinator(MiningConfig::default()).expect("test assertion");
+        let job = MiningJob {
+            target: HashTarget { target: [0xFF; 32] },
+            version_mask: 0x1fff_e000,
+            ..genesis_job()
+        };
+        let worker = spawn_worker(&coordinator, job.clone(), 0..=1);
+        wait_for_shares(&coordinator, 64);
+        coordinator.stop();
+        worker.join().expect("test assertion");
+
+        let shares = coordinator.take_found_shares();
+        let mut versions: Vec<u32> = shares.iter().map(|share| share.version).collect();
+        assert!(versions.iter().all(|version| version & !job.version_mask == 1));
+        versions.dedup();
+        assert!(versions.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(versions[..3], [1, 0x2001, 0x4001]);
+
+        let share = &shares[shares.len() - 1];
+        let mut header = job.header.clone();
+        header.version = share.version;
+        header.nonce = share.nonce;
+        let mut hash = double_sha256(&header.serialize());
+        hash.reverse();
+        assert_eq!(share.hash, hash);
+    }
+
+    #[test]
+    fn test_exhausted_range_rolls_extranonce2_and_ntime() {
+        let coordinator = mining_coordinator(MiningConfig::default()).expect("test assertion");
+        let template = JobTemplate {
+            job_id:        "roll".into(),
+            prev_hash:     [0; 32],
+            coinbase1:     vec![0x01, 0x02],
+            coinbase2:     vec![0x03],
+            merkle_branch: vec![[0x44; 32]],
+            version:       0x2000_0000,
+            nbits:         0x1d00_ffff,
+            ntime:         1_700_000_000,
+            clean_jobs:    true,
+        };
+        let mut job = template.to_job(&[0xAA], vec![0xFE], HashTarget { target: [0xFF; 32] });
+        job.max_ntime = template.ntime + 1;
+
+        // Two nonces per header, extranonce 2 0xFE and 0xFF at the job's
+        // ntime, then all 256 values one second later.
+        let worker = spawn_worker(&coordinator, job, 0..=1);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while coordinator.stats().total_hashes < 516 {
+            assert!(Instant::now() < deadline, "worker stopped rolling");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        coordinator.stop();
+        worker.join().expect("test assertion");
+        assert_eq!(coordinator.stats().total_hashes, 516);
+
+        let shares = coordinator.take_found_shares();
+        assert_eq!(shares.len(), 516);
+        let work: Vec<(u8, u32, u32)> = shares
+            .iter()
+            .map(|share| (share.extranonce2[0], share.ntime - template.ntime, share.nonce))
+            .collect();
+        assert_eq!(work[..5], [(0xFE, 0, 0), (0xFE, 0, 1), (0xFF, 0, 0), (0xFF, 0, 1), (0, 1, 0)]);
+        assert_eq!(work[515], (0xFF, 1, 1));
+
+        for share in [&shares[0], &shares[4], &shares[515]] {
+            let mut header = template.header(&[0xAA], &share.extranonce2);
+            header.timestamp = share.ntime;
+            header.nonce = share.nonce;
+            let mut hash = double_sha256(&header.serialize());
+            hash.reverse();
+            assert_eq!(share.hash, hash);
+        }
+    }
 }
@@ -1,9 +1,26 @@
 //! Hardware detection integration for mining optimization.
 //!
-//! This module integrates with `essentia_hwdetect` to determine optimal
-//! mining parameters based on available hardware capabilities.
+//! This module determines optimal mining parameters based on available
+//! hardware capabilities. On Linux the CPU topology comes from
+//! `/sys/devices/system/cpu` (or `/proc/cpuinfo`) and memory from
+//! `/proc/meminfo`; instruction set extensions come from CPUID.
+//!
+//! Core and thread counts describe the machine. Inside a container, the
+//! cgroup's CPU quota, cpuset and memory limit narrow what mining may use:
+//! [`MiningHardwareProfile::cpu_allowance`] and
+//! [`MiningHardwareProfile::available_cores`] size the workers, and the
+//! memory figures are capped at the cgroup limit.
+
+use std::path::Path;
+
+use crate::{
+    errors::{MiningError, MiningResult},
+    r#impl::{CgroupLimits, CpuTopology},
+    traits::MiningHardwareTrait,
+    types::{CoreClass, CorePreference},
+};
 
-use crate::{errors::MiningResult, traits::MiningHardwareTrait};
+const GIB: u64 = 1024 * 1024 * 1024;
 
 /// Hardware profile for mining optimization.
 #[derive(Debug, Clone)]
@@ -14,16 +31,24 @@ pub struct MiningHardwareProfile {
     pub logical_cores:      usize,
     /// Available system memory in bytes.
     pub available_memory:   u64,
+    /// Total system memory in bytes.
+    pub total_memory:       u64,
     /// CPU supports SHA extensions.
     pub has_sha_extensions: bool,
     /// CPU supports AVX2.
     pub has_avx2:           bool,
+    /// CPU supports SSE4.1.
+    pub has_sse41:          bool,
     /// GPU available for mining.
     pub gpu_available:      bool,
     /// GPU compute capability (if available).
     pub gpu_compute_units:  Option<u32>,
     /// Performance tier (1-5, 5 being highest).
     pub performance_tier:   u8,
+    /// Which logical CPUs share a physical core, if known.
+    pub topology:           Option<CpuTopology>,
+    /// Limits of the cgroup the process runs in.
+    pub cgroup:             CgroupLimits,
 }
 
 impl MiningHardwareProfile {
@@ -33,38 +58,164 @@ impl MiningHardwareProfile {
     ///
     /// Returns `MiningError::HardwareDetection` if hardware detection fails.
     pub fn detect() -> MiningResult<Self> {
-        // In production, this would call essentia_hwdetect
-        // For now, use basic detection
-        Self::detect_basic()
+        #[cfg(target_os = "linux")]
+        {
+            Self::detect_from_root(Path::new("/"))
+        }
+        #[cfg(not(target_os = "linux"))]
+        {
+            Self::detect_basic()
+        }
     }
 
-    /// Basic hardware detection without external dependencies.
-    fn detect_basic() -> MiningResult<Self> {
-        // Detect CPU cores
-        let logical_cores = detect_cpu_cores();
-        let physical_cores = logical_cores / 2; // Assume hyperthreading
+    /// Detect hardware and cgroup limits from the `proc` and `sys` trees
+    /// under `root`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MiningError::HardwareDetection` if neither sysfs nor
+    /// `/proc/cpuinfo` lists any CPU, or `/proc/meminfo` cannot be read.
+    pub fn detect_from_root(root: &Path) -> MiningResult<Self> {
+        let topology = CpuTopology::from_sysfs(&root.join("sys"))
+            .or_else(|| {
+                let cpuinfo = std::fs::read_to_string(root.join("proc/cpuinfo")).ok()?;
+                CpuTopology::from_cpuinfo(&cpuinfo)
+            })
+            .ok_or_else(|| MiningError::HardwareDetection("No CPU topology found".into()))?;
 
-        // Detect memory (rough estimate)
-        let available_memory = detect_available_memory();
+        let meminfo_path = root.join("proc/meminfo");
+        let meminfo = std::fs::read_to_string(&meminfo_path).map_err(|e| {
+            MiningError::HardwareDetection(format!(
+                "Failed to read {}: {e}",
+                meminfo_path.display()
+            ))
+        })?;
+        let (total_memory, available_memory) = parse_meminfo(&meminfo)?;
 
-        // CPU feature detection would go here
-        // For now, assume conservative values
-        let has_sha_extensions = false;
-        let has_avx2 = false;
+        Ok(Self::from_parts(
+            topology.physical_cores(),
+            topology.logical_cores(),
+            total_memory,
+            available_memory,
+            Some(topology),
+            CgroupLimits::from_root(root),
+        ))
+    }
 
-        // Calculate performance tier
-        let performance_tier = Self::calculate_tier(logical_cores, available_memory);
+    /// Hardware detection without OS-specific sources.
+    ///
+    /// The standard library reports logical CPUs only, so each counts as a
+    /// physical core; memory is assumed to be 8 GiB.
+    #[cfg(not(target_os = "linux"))]
+    fn detect_basic() -> MiningResult<Self> {
+        let logical_cores = std::thread::available_parallelism().map_or(1, usize::from);
+        Ok(Self::from_parts(
+            logical_cores,
+            logical_cores,
+            8 * GIB,
+            8 * GIB,
+            None,
+            CgroupLimits::default(),
+        ))
+    }
 
-        Ok(Self {
+    fn from_parts(
+        physical_cores: usize, logical_cores: usize, total_memory: u64, available_memory: u64,
+        topology: Option<CpuTopology>, cgroup: CgroupLimits,
+    ) -> Self {
+        let CpuFeatures { sha, avx2, sse41 } = CpuFeatures::detect();
+        let mut profile = Self {
             physical_cores: physical_cores.max(1),
             logical_cores: logical_cores.max(1),
-            available_memory,
-            has_sha_extensions,
-            has_avx2,
+            available_memory: cgroup
+                .memory_available()
+                .map_or(available_memory, |allowed| allowed.min(available_memory)),
+            total_memory: cgroup.memory_limit.map_or(total_memory, |limit| limit.min(total_memory)),
+            has_sha_extensions: sha,
+            has_avx2: avx2,
+            has_sse41: sse41,
             gpu_available: false,
             gpu_compute_units: None,
-            performance_tier,
-        })
+            performance_tier: 1,
+            topology,
+            cgroup,
+        };
+        profile.performance_tier =
+            Self::calculate_tier(profile.allowed_cpus().len(), profile.total_memory);
+        profile
+    }
+
+    /// Logical CPUs the process may run on, in CPU number order.
+    fn allowed_cpus(&self) -> Vec<usize> {
+        let cpus: Vec<usize> = match self.topology {
+            Some(ref topology) => topology.cpus().iter().map(|cpu| cpu.id).collect(),
+            None => (0..self.logical_cores.max(self.physical_cores)).collect(),
+        };
+        match self.cgroup.cpuset {
+            Some(ref cpuset) => cpus.into_iter().filter(|cpu| cpuset.contains(cpu)).collect(),
+            None => cpus,
+        }
+    }
+
+    /// CPU capacity mining may use, in cores.
+    ///
+    /// Physical cores within the cgroup's cpuset, capped by its CPU quota.
+    /// A quota of 1.5 CPUs on an eight-core host gives 1.5.
+    #[must_use]
+    pub fn cpu_allowance(&self) -> f64 {
+        let cores = match (&self.cgroup.cpuset, &self.topology) {
+            (None, _) => self.physical_cores,
+            (Some(_), Some(topology)) => topology.physical_cores_among(&self.allowed_cpus()),
+            (Some(_), None) => self.allowed_cpus().len().min(self.physical_cores),
+        };
+        let cores = cores.max(1) as f64;
+        self.cgroup.cpu_quota.map_or(cores, |quota| quota.min(cores))
+    }
+
+    /// Whole cores to size the worker count against: the CPU allowance
+    /// rounded up, so a fractional quota gets a thread on a duty cycle.
+    #[must_use]
+    pub fn available_cores(&self) -> usize {
+        (self.cpu_allowance().ceil() as usize).max(1)
+    }
+
+    /// Logical CPUs available for mining, in order.
+    ///
+    /// Taking CPUs from the front spreads workers over physical cores: with
+    /// a known topology the first thread of every core comes before any
+    /// hyperthread sibling. Otherwise the usual Linux numbering is assumed,
+    /// where CPUs `0..physical_cores` are on distinct cores. CPUs outside
+    /// the cgroup's cpuset are left out.
+    #[must_use]
+    pub fn mining_cores(&self, excluded: &[usize]) -> Vec<usize> {
+        let order = match self.topology {
+            Some(ref topology) => topology.spread_order(),
+            None => (0..self.logical_cores.max(self.physical_cores)).collect(),
+        };
+        let allowed =
+            |core: &usize| self.cgroup.cpuset.as_ref().is_none_or(|cpuset| cpuset.contains(core));
+        order.into_iter().filter(|core| allowed(core) && !excluded.contains(core)).collect()
+    }
+
+    /// Mining cores for `preference`, in the order of
+    /// [`Self::mining_cores`].
+    ///
+    /// On a hybrid CPU, a preference keeps only cores of that class, unless
+    /// `excluded` leaves none of them. Elsewhere it has no effect.
+    #[must_use]
+    pub fn preferred_cores(&self, preference: CorePreference, excluded: &[usize]) -> Vec<usize> {
+        let cores = self.mining_cores(excluded);
+        let class = match preference {
+            CorePreference::Any => return cores,
+            CorePreference::Efficiency => CoreClass::Efficiency,
+            CorePreference::Performance => CoreClass::Performance,
+        };
+        let preferred: Vec<usize> = cores
+            .iter()
+            .copied()
+            .filter(|&cpu| self.is_hybrid() && self.core_class(cpu) == Some(class))
+            .collect();
+        if preferred.is_empty() { cores } else { preferred }
     }
 
     /// Calculate performance tier based on hardware.
@@ -77,7 +228,7 @@ impl MiningHardwareProfile {
             _ => 5,
         };
 
-        let mem_gb = memory / (1024 * 1024 * 1024);
+        let mem_gb = memory / GIB;
         let mem_score = match mem_gb {
             0..=4 => 1,
             5..=8 => 2,
@@ -100,40 +251,77 @@ impl MiningHardwareTrait for MiningHardwareProfile {
     }
 
     fn is_suitable_for_mining(&self) -> bool {
-        self.physical_cores >= 2 && self.available_memory >= 2 * 1024 * 1024 * 1024
+        self.cpu_allowance() >= 2.0 && self.available_memory >= 2 * GIB
     }
 
     fn recommended_threads(&self, max_percentage: u8) -> usize {
-        let available = self.physical_cores;
+        let available = self.available_cores();
         let target = (available as f64 * (max_percentage as f64 / 100.0)) as usize;
         target.max(1).min(available)
     }
+
+    fn is_hybrid(&self) -> bool {
+        self.topology.as_ref().is_some_and(CpuTopology::is_hybrid)
+    }
+
+    fn core_class(&self, cpu: usize) -> Option<CoreClass> {
+        Some(self.topology.as_ref()?.cpu(cpu)?.class)
+    }
+
+    fn core_capacity(&self, cpu: usize) -> Option<u32> {
+        Some(self.topology.as_ref()?.cpu(cpu)?.capacity)
+    }
 }
 
-/// Detect number of CPU cores.
-fn detect_cpu_cores() -> usize {
-    // Simple heuristic: check environment or use default
-    // In production, use essentia_hwdetect
+/// Hashing-relevant instruction set extensions of the running CPU.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct CpuFeatures {
+    /// SHA-256 round instructions, with the SSSE3/SSE4.1 shuffles they
+    /// need.
+    sha:   bool,
+    avx2:  bool,
+    sse41: bool,
+}
 
-    #[cfg(target_os = "windows")]
-    {
-        // Try to get from environment
-        if let Ok(val) = std::env::var("NUMBER_OF_PROCESSORS")
-            && let Ok(n) = val.parse::<usize>()
-        {
-            return n;
+impl CpuFeatures {
+    /// Query CPUID; the standard library also checks that the OS saves the
+    /// AVX register state.
+    #[cfg(target_arch = "x86_64")]
+    fn detect() -> Self {
+        let sse41 = std::is_x86_feature_detected!("sse4.1");
+        Self {
+            sha: sse41
+                && std::is_x86_feature_detected!("ssse3")
+                && std::is_x86_feature_detected!("sha"),
+            avx2: std::is_x86_feature_detected!("avx2"),
+            sse41,
         }
     }
 
-    // Default fallback
-    4
+    #[cfg(not(target_arch = "x86_64"))]
+    fn detect() -> Self {
+        Self::default()
+    }
 }
 
-/// Detect available system memory.
-fn detect_available_memory() -> u64 {
-    // In production, use essentia_hwdetect
-    // Default to 8GB assumption
-    8 * 1024 * 1024 * 1024
+/// Total and available memory in bytes from `/proc/meminfo`.
+///
+/// Kernels older than 3.14 lack `MemAvailable`; free memory plus page cache
+/// approximates it there.
+fn parse_meminfo(meminfo: &str) -> MiningResult<(u64, u64)> {
+    let field = |key: &str| {
+        meminfo.lines().find_map(|line| {
+            let value = line.strip_prefix(key)?.strip_prefix(':')?;
+            let kib: u64 = value.trim().trim_end_matches("kB").trim().parse().ok()?;
+            Some(kib * 1024)
+        })
+    };
+    let total = field("MemTotal")
+        .ok_or_else(|| MiningError::HardwareDetection("No MemTotal in /proc/meminfo".into()))?;
+    let available = field("MemAvailable")
+        .or_else(|| Some(field("MemFree")? + field("Cached").unwrap_or(0)))
+        .unwrap_or(total);
+    Ok((total, available.min(total)))
 }
 
 impl Default for MiningHardwareProfile {
@@ -141,19 +329,41 @@ impl Default for MiningHardwareProfile {
         Self::detect().unwrap_or(Self {
             physical_cores:     2,
             logical_cores:      4,
-            available_memory:   4 * 1024 * 1024 * 1024,
+            available_memory:   4 * GIB,
+            total_memory:       4 * GIB,
             has_sha_extensions: false,
             has_avx2:           false,
+            has_sse41:          false,
             gpu_available:      false,
             gpu_compute_units:  None,
             performance_tier:   2,
+            topology:           None,
+            cgroup:             CgroupLimits::default(),
         })
     }
 }
 
+#[cfg(all(test, feature = "full-tests"))]
+impl MiningHardwareProfile {
+    /// Two cores with hyperthreading and 8 GiB, whatever the test machine
+    /// has.
+    pub(crate) fn test_machine() -> Self {
+        Self {
+            physical_cores: 2,
+            logical_cores: 4,
+            available_memory: 8 * GIB,
+            total_memory: 8 * GIB,
+            topology: None,
+            cgroup: CgroupLimits::default(),
+            ..Self::default()
+        }
+    }
+}
+
 #[cfg(all(test, feature = "full-tests"))]
 mod tests {
     use super::*;
+    use crate::r#impl::{LogicalCpu, MiningConfig, test_tree::TempTree};
 
     #[test]
     fn test_hardware_detection() {
@@ -161,16 +371,177 @@ mod tests {
         assert!(profile.is_ok());
     }
 
+    /// A profile of `physical_cores` cores with two threads each, outside
+    /// any cgroup, whatever the test machine has.
+    fn machine(physical_cores: usize) -> MiningHardwareProfile {
+        MiningHardwareProfile {
+            physical_cores,
+            logical_cores: physical_cores * 2,
+            available_memory: 8 * GIB,
+            topology: None,
+            cgroup: CgroupLimits::default(),
+            ..Default::default()
+        }
+    }
+
     #[test]
     fn test_recommended_threads() {
-        let profile =
-            MiningHardwareProfile { physical_cores: 8, logical_cores: 16, ..Default::default() };
+        let profile = machine(8);
 
         assert_eq!(profile.recommended_threads(50), 4);
         assert_eq!(profile.recommended_threads(25), 2);
         assert_eq!(profile.recommended_threads(100), 8);
     }
 
+    #[test]
+    #[cfg(target_arch = "x86_64")]
+    fn test_cpu_features_match_cpuid() {
+        let profile = MiningHardwareProfile::detect().expect("test assertion");
+        assert_eq!(profile.has_avx2, std::is_x86_feature_detected!("avx2"));
+        assert_eq!(profile.has_sse41, std::is_x86_feature_detected!("sse4.1"));
+        assert_eq!(
+            profile.has_sha_extensions,
+            std::is_x86_feature_detected!("sha") && std::is_x86_feature_detected!("sse4.1")
+        );
+    }
+
+    /// Fake root with `/proc/cpuinfo` and `/proc/meminfo` of a four-core,
+    /// eight-thread desktop with 32 GiB, and optionally its sysfs topology.
+    fn fake_root(sysfs: bool) -> TempTree {
+        let root = TempTree::with_files("hardware", &[("proc/meminfo", MEMINFO)]);
+        root.write("proc/cpuinfo", cpuinfo());
+        if sysfs {
+            root.write("sys/devices/system/cpu/online", "0-7\n");
+            for id in 0..8 {
+                let topology = format!("sys/devices/system/cpu/cpu{id}/topology");
+                root.write(format!("{topology}/physical_package_id"), "0\n");
+                root.write(format!("{topology}/core_id"), format!("{}\n", id % 4));
+            }
+        }
+        root
+    }
+
+    const MEMINFO: &str = "\
+MemTotal:       32803520 kB
+MemFree:         1204388 kB
+MemAvailable:   20971520 kB
+Buffers:          826660 kB
+Cached:         15104972 kB
+";
+
+    fn cpuinfo() -> String {
+        use std::fmt::Write;
+
+        let mut cpuinfo = String::new();
+        for id in 0..8 {
+            write!(
+                cpuinfo,
+                "processor\t: {id}\nvendor_id\t: AuthenticAMD\nphysical id\t: 0\n\
+                 siblings\t: 8\ncore id\t\t: {}\ncpu cores\t: 4\n\n",
+                id % 4
+            )
+            .expect("test assertion");
+        }
+        cpuinfo
+    }
+
+    #[test]
+    fn test_detect_from_sysfs_and_meminfo() {
+        let root = fake_root(true);
+        let profile = MiningHardwareProfile::detect_from_root(root.path()).expect("test assertion");
+        assert_eq!(profile.logical_cores, 8);
+        assert_eq!(profile.physical_cores, 4);
+        assert_eq!(profile.total_memory, 32_803_520 * 1024);
+        assert_eq!(profile.available_memory, 20 * GIB);
+        assert_eq!(profile.performance_tier, 3);
+        assert_eq!(profile.mining_cores(&[1]), [0, 2, 3, 4, 5, 6, 7]);
+        assert!(profile.is_suitable_for_mining());
+    }
+
+    #[test]
+    fn test_detect_falls_back_to_cpuinfo() {
+        let root = fake_root(false);
+        let profile = MiningHardwareProfile::detect_from_root(root.path()).expect("test assertion");
+        assert_eq!(profile.logical_cores, 8);
+        assert_eq!(profile.physical_cores, 4);
+
+        std::fs::remove_file(root.join("proc/cpuinfo")).expect("test assertion");
+        assert!(matches!(
+            MiningHardwareProfile::detect_from_root(root.path()),
+            Err(MiningError::HardwareDetection(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_meminfo() {
+        let (total, available) = parse_meminfo(MEMINFO).expect("test assertion");
+        assert_eq!(total, 32_803_520 * 1024);
+        assert_eq!(available, 20 * GIB);
+
+        // No MemAvailable before Linux 3.14.
+        let (_, available) = parse_meminfo("MemTotal: 4096 kB\nMemFree: 1024 kB\nCached: 512 kB\n")
+            .expect("test assertion");
+        assert_eq!(available, 1536 * 1024);
+        assert!(parse_meminfo("MemFree: 1024 kB\n").is_err());
+    }
+
+    #[test]
+    fn test_cgroup_quota_and_cpuset_size_the_allowance() {
+        let mut profile = machine(8);
+        profile.cgroup.cpu_quota = Some(1.5);
+        assert!((profile.cpu_allowance() - 1.5).abs() < 1e-9);
+        assert_eq!(profile.available_cores(), 2);
+        assert_eq!(profile.recommended_threads(100), 2);
+        assert!(!profile.is_suitable_for_mining());
+        assert_eq!(MiningConfig::default().effective_thread_count(profile.available_cores()), 1);
+
+        // CPUs 2 and 10 are siblings of one core.
+        let mut profile = machine(8);
+        profile.topology =
+            CpuTopology::new((0..16).map(|id| LogicalCpu::new(id, 0, id % 8)).collect());
+        profile.cgroup.cpuset = Some(vec![2, 3, 10]);
+        assert!((profile.cpu_allowance() - 2.0).abs() < 1e-9);
+        assert!(profile.is_suitable_for_mining());
+        assert_eq!(profile.mining_cores(&[]), [2, 3, 10]);
+        assert_eq!(profile.mining_cores(&[3]), [2, 10]);
+    }
+
+    #[test]
+    fn test_detect_applies_cgroup_limits() {
+        let root = fake_root(true);
+        for (path, contents) in [
+            ("proc/self/cgroup", "0::/miner\n"),
+            ("sys/fs/cgroup/cgroup.controllers", "cpuset cpu memory\n"),
+            ("sys/fs/cgroup/miner/cpu.max", "300000 100000\n"),
+            ("sys/fs/cgroup/miner/cpuset.cpus.effective", "0-1,4-5\n"),
+            ("sys/fs/cgroup/miner/memory.max", "3221225472\n"),
+            ("sys/fs/cgroup/miner/memory.current", "1073741824\n"),
+        ] {
+            root.write(path, contents);
+        }
+
+        let profile = MiningHardwareProfile::detect_from_root(root.path()).expect("test assertion");
+        assert_eq!(profile.physical_cores, 4);
+        assert_eq!(profile.total_memory, 3 * GIB);
+        assert_eq!(profile.available_memory, 2 * GIB);
+        // Four CPUs on two cores; the quota of three CPUs does not bind.
+        assert!((profile.cpu_allowance() - 2.0).abs() < 1e-9);
+        assert_eq!(profile.mining_cores(&[]), [0, 1, 4, 5]);
+        assert_eq!(profile.performance_tier, 1);
+        assert!(profile.is_suitable_for_mining());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_detect_reads_this_machine() {
+        let profile = MiningHardwareProfile::detect().expect("test assertion");
+        let topology = profile.topology.as_ref().expect("test assertion");
+        assert_eq!(profile.logical_cores, topology.logical_cores());
+        assert!(profile.physical_cores <= profile.logical_cores);
+        assert!(profile.available_memory <= profile.total_memory);
+        assert!(profile.total_memory > 0);
+    }
+
     #[test]
     fn test_performance_tier() {
         let tier = MiningHardwareProfile::calculate_tier(16, 32 * 1024 * 1024 * 1024);
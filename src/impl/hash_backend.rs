@@ -0,0 +1,658 @@
+//! SHA-256d hashing backends selected from CPU features.
+//!
+//! | Backend        | Lanes | Requires            |
+//! |----------------|-------|---------------------|
+//! | [`ShaNiBackend`]  | 2     | SHA extensions, SSE4.1 |
+//! | [`Avx2Backend`]   | 8     | AVX2                |
+//! | [`Sse41Backend`]  | 4     | SSE4.1              |
+//! | [`ScalarBackend`] | 1     | -                   |
+//!
+//! [`select_hash_backend`] picks the first one the hardware profile reports
+//! and the running CPU confirms. The vector backends hash one nonce per
+//! 32-bit lane, running the same rounds as [`HeaderHasher`] on every lane at
+//! once; the SHA-NI backend runs two nonces back to back through the
+//! dedicated round instructions. All of them start from the
+//! [`HeaderHasher`] midstate and must produce the same digests as
+//! `double_sha256`.
+
+use std::sync::Arc;
+
+use crate::{
+    r#impl::{HeaderHasher, MiningHardwareProfile},
+    types::HashTarget,
+};
+
+/// Largest `HashBackend::lanes` of any backend.
+pub const MAX_HASH_LANES: usize = 8;
+
+/// SHA-256d header hashing backend.
+pub trait HashBackend: Send + Sync {
+    /// Short backend name, e.g. `"avx2"`.
+    fn name(&self) -> &'static str;
+
+    /// Number of nonces hashed per batch, at most [`MAX_HASH_LANES`].
+    fn lanes(&self) -> usize;
+
+    /// Hash `lanes()` consecutive nonces starting at `first_nonce`.
+    ///
+    /// Writes the `double_sha256` digest of each header, in digest byte
+    /// order, to the first `lanes()` entries of `out`. Nonces wrap at
+    /// `u32::MAX`.
+    fn hash_batch(&self, header: &HeaderHasher, first_nonce: u32, out: &mut [[u8; 32]]);
+
+    /// Hash `count` nonces from `first_nonce` and report those that meet
+    /// `target`, with the hash most significant byte first.
+    ///
+    /// `count` is capped at `lanes()`.
+    fn scan(
+        &self, header: &HeaderHasher, first_nonce: u32, count: usize, target: &HashTarget,
+        found: &mut dyn FnMut(u32, [u8; 32]),
+    ) {
+        let mut digests = [[0u8; 32]; MAX_HASH_LANES];
+        let lanes = self.lanes().min(MAX_HASH_LANES);
+        self.hash_batch(header, first_nonce, &mut digests[..lanes]);
+        for (nonce, digest) in (0..count.min(lanes) as u32).zip(digests) {
+            let mut hash = digest;
+            hash.reverse();
+            if target.is_valid_hash(&hash) {
+                found(first_nonce.wrapping_add(nonce), hash);
+            }
+        }
+    }
+}
+
+/// Portable backend on top of [`HeaderHasher`], with early exit.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScalarBackend;
+
+impl HashBackend for ScalarBackend {
+    fn name(&self) -> &'static str {
+        "scalar"
+    }
+
+    fn lanes(&self) -> usize {
+        1
+    }
+
+    fn hash_batch(&self, header: &HeaderHasher, first_nonce: u32, out: &mut [[u8; 32]]) {
+        out[0] = header.hash(first_nonce);
+    }
+
+    fn scan(
+        &self, header: &HeaderHasher, first_nonce: u32, count: usize, target: &HashTarget,
+        found: &mut dyn FnMut(u32, [u8; 32]),
+    ) {
+        if count > 0
+            && let Some(hash) = header.check(first_nonce, target)
+        {
+            found(first_nonce, hash);
+        }
+    }
+}
+
+/// Four nonces per call in SSE registers.
+#[derive(Debug, Clone, Copy)]
+pub struct Sse41Backend {
+    _detected: (),
+}
+
+/// Eight nonces per call in AVX2 registers.
+#[derive(Debug, Clone, Copy)]
+pub struct Avx2Backend {
+    _detected: (),
+}
+
+/// Two nonces per call through the SHA extensions.
+#[derive(Debug, Clone, Copy)]
+pub struct ShaNiBackend {
+    _detected: (),
+}
+
+impl Sse41Backend {
+    /// The backend, if the CPU supports SSE4.1.
+    #[must_use]
+    pub fn detect() -> Option<Self> {
+        #[cfg(target_arch = "x86_64")]
+        if std::is_x86_feature_detected!("sse4.1") {
+            return Some(Self { _detected: () });
+        }
+        None
+    }
+}
+
+impl Avx2Backend {
+    /// The backend, if the CPU supports AVX2.
+    #[must_use]
+    pub fn detect() -> Option<Self> {
+        #[cfg(target_arch = "x86_64")]
+        if std::is_x86_feature_detected!("avx2") {
+            return Some(Self { _detected: () });
+        }
+        None
+    }
+}
+
+impl ShaNiBackend {
+    /// The backend, if the CPU supports the SHA extensions.
+    #[must_use]
+    pub fn detect() -> Option<Self> {
+        #[cfg(target_arch = "x86_64")]
+        if std::is_x86_feature_detected!("sha")
+            && std::is_x86_feature_detected!("ssse3")
+            && std::is_x86_feature_detected!("sse4.1")
+        {
+            return Some(Self { _detected: () });
+        }
+        None
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+impl HashBackend for Sse41Backend {
+    fn name(&self) -> &'static str {
+        "sse4.1"
+    }
+
+    fn lanes(&self) -> usize {
+        4
+    }
+
+    fn hash_batch(&self, header: &HeaderHasher, first_nonce: u32, out: &mut [[u8; 32]]) {
+        // SAFETY: `Sse41Backend` is only built by `detect`, after checking
+        // that the CPU supports SSE4.1.
+        unsafe { x86::hash_sse41(header, first_nonce, out) }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+impl HashBackend for Avx2Backend {
+    fn name(&self) -> &'static str {
+        "avx2"
+    }
+
+    fn lanes(&self) -> usize {
+        8
+    }
+
+    fn hash_batch(&self, header: &HeaderHasher, first_nonce: u32, out: &mut [[u8; 32]]) {
+        // SAFETY: `Avx2Backend` is only built by `detect`, after checking
+        // that the CPU supports AVX2.
+        unsafe { x86::hash_avx2(header, first_nonce, out) }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+impl HashBackend for ShaNiBackend {
+    fn name(&self) -> &'static str {
+        "sha-ni"
+    }
+
+    fn lanes(&self) -> usize {
+        2
+    }
+
+    fn hash_batch(&self, header: &HeaderHasher, first_nonce: u32, out: &mut [[u8; 32]]) {
+        for (lane, digest) in (0..2u32).zip(out.iter_mut()) {
+            // SAFETY: `ShaNiBackend` is only built by `detect`, after
+            // checking that the CPU supports SHA, SSSE3 and SSE4.1.
+            *digest = unsafe { x86::hash_sha_ni(header, first_nonce.wrapping_add(lane)) };
+        }
+    }
+}
+
+/// Fastest backend that `profile` reports and the running CPU supports.
+#[must_use]
+pub fn select_hash_backend(profile: &MiningHardwareProfile) -> Arc<dyn HashBackend> {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if profile.has_sha_extensions
+            && let Some(backend) = ShaNiBackend::detect()
+        {
+            return Arc::new(backend);
+        }
+        if profile.has_avx2
+            && let Some(backend) = Avx2Backend::detect()
+        {
+            return Arc::new(backend);
+        }
+        if profile.has_sse41
+            && let Some(backend) = Sse41Backend::detect()
+        {
+            return Arc::new(backend);
+        }
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    let _ = profile;
+    Arc::new(ScalarBackend)
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    //! Lane-parallel SHA-256d and the SHA-NI compression function.
+    //!
+    //! The lane types wrap a register and are only created inside the
+    //! `#[target_feature]` entry points, which callers reach after runtime
+    //! detection; their methods are always inlined into those functions.
+    #![allow(clippy::inline_always)]
+
+    use core::arch::x86_64::{
+        __m128i, __m256i, _mm_add_epi32, _mm_alignr_epi8, _mm_and_si128, _mm_andnot_si128,
+        _mm_extract_epi32, _mm_or_si128, _mm_set1_epi32, _mm_setr_epi32, _mm_sha256msg1_epu32,
+        _mm_sha256msg2_epu32, _mm_sha256rnds2_epu32, _mm_shuffle_epi32, _mm_slli_epi32,
+        _mm_srli_epi32, _mm_storeu_si128, _mm_xor_si128, _mm256_add_epi32, _mm256_and_si256,
+        _mm256_andnot_si256, _mm256_or_si256, _mm256_set1_epi32, _mm256_setr_epi32,
+        _mm256_slli_epi32, _mm256_srli_epi32, _mm256_storeu_si256, _mm256_xor_si256,
+    };
+
+    use crate::r#impl::{
+        HeaderHasher,
+        midstate::{IV, K, NONCE_WORD},
+    };
+
+    /// 32-bit lanes of one vector register.
+    trait Lanes: Copy {
+        const COUNT: usize;
+
+        fn splat(value: u32) -> Self;
+        /// Byte-swapped nonces `first..first + COUNT`, as SHA-256 reads them.
+        fn nonces(first: u32) -> Self;
+        fn add(self, other: Self) -> Self;
+        fn xor(self, other: Self) -> Self;
+        fn and(self, other: Self) -> Self;
+        fn or(self, other: Self) -> Self;
+        /// `!self & other`.
+        fn andnot(self, other: Self) -> Self;
+        fn shr<const BITS: i32>(self) -> Self;
+        fn shl<const BITS: i32>(self) -> Self;
+        /// Write the lanes to `out[..COUNT]`.
+        fn store(self, out: &mut [u32; 8]);
+
+        /// Rotate right by `BITS`; `LEFT` must be `32 - BITS`.
+        #[inline(always)]
+        fn rotr<const BITS: i32, const LEFT: i32>(self) -> Self {
+            self.shr::<BITS>().or(self.shl::<LEFT>())
+        }
+    }
+
+    fn swapped_nonce(first: u32, lane: u32) -> i32 {
+        first.wrapping_add(lane).swap_bytes().cast_signed()
+    }
+
+    #[derive(Clone, Copy)]
+    struct Sse(__m128i);
+
+    #[derive(Clone, Copy)]
+    struct Avx(__m256i);
+
+    macro_rules! lanes_impl {
+        ($lanes:ty, $count:expr, $set1:ident, $add:ident, $xor:ident, $and:ident, $or:ident,
+         $andnot:ident, $srli:ident, $slli:ident, $store:ident, $nonces:expr) => {
+            // SAFETY (all methods): values of this type only exist inside
+            // functions compiled for, and only called on, CPUs with the
+            // instructions used here.
+            impl Lanes for $lanes {
+                const COUNT: usize = $count;
+
+                #[inline(always)]
+                fn splat(value: u32) -> Self {
+                    Self(unsafe { $set1(value.cast_signed()) })
+                }
+
+                #[inline(always)]
+                fn nonces(first: u32) -> Self {
+                    Self(unsafe { $nonces(first) })
+                }
+
+                #[inline(always)]
+                fn add(self, other: Self) -> Self {
+                    Self(unsafe { $add(self.0, other.0) })
+                }
+
+                #[inline(always)]
+                fn xor(self, other: Self) -> Self {
+                    Self(unsafe { $xor(self.0, other.0) })
+                }
+
+                #[inline(always)]
+                fn and(self, other: Self) -> Self {
+                    Self(unsafe { $and(self.0, other.0) })
+                }
+
+                #[inline(always)]
+                fn or(self, other: Self) -> Self {
+                    Self(unsafe { $or(self.0, other.0) })
+                }
+
+                #[inline(always)]
+                fn andnot(self, other: Self) -> Self {
+                    Self(unsafe { $andnot(self.0, other.0) })
+                }
+
+                #[inline(always)]
+                fn shr<const BITS: i32>(self) -> Self {
+                    Self(unsafe { $srli::<BITS>(self.0) })
+                }
+
+                #[inline(always)]
+                fn shl<const BITS: i32>(self) -> Self {
+                    Self(unsafe { $slli::<BITS>(self.0) })
+                }
+
+                #[inline(always)]
+                fn store(self, out: &mut [u32; 8]) {
+                    // `out` holds at least `COUNT` lanes.
+                    unsafe { $store(out.as_mut_ptr().cast(), self.0) }
+                }
+            }
+        };
+    }
+
+    lanes_impl!(
+        Sse,
+        4,
+        _mm_set1_epi32,
+        _mm_add_epi32,
+        _mm_xor_si128,
+        _mm_and_si128,
+        _mm_or_si128,
+        _mm_andnot_si128,
+        _mm_srli_epi32,
+        _mm_slli_epi32,
+        _mm_storeu_si128,
+        |first| _mm_setr_epi32(
+            swapped_nonce(first, 0),
+            swapped_nonce(first, 1),
+            swapped_nonce(first, 2),
+            swapped_nonce(first, 3),
+        )
+    );
+
+    lanes_impl!(
+        Avx,
+        8,
+        _mm256_set1_epi32,
+        _mm256_add_epi32,
+        _mm256_xor_si256,
+        _mm256_and_si256,
+        _mm256_or_si256,
+        _mm256_andnot_si256,
+        _mm256_srli_epi32,
+        _mm256_slli_epi32,
+        _mm256_storeu_si256,
+        |first| _mm256_setr_epi32(
+            swapped_nonce(first, 0),
+            swapped_nonce(first, 1),
+            swapped_nonce(first, 2),
+            swapped_nonce(first, 3),
+            swapped_nonce(first, 4),
+            swapped_nonce(first, 5),
+            swapped_nonce(first, 6),
+            swapped_nonce(first, 7),
+        )
+    );
+
+    #[target_feature(enable = "sse4.1")]
+    pub(super) fn hash_sse41(header: &HeaderHasher, first_nonce: u32, out: &mut [[u8; 32]]) {
+        hash_lanes::<Sse>(header, first_nonce, out);
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub(super) fn hash_avx2(header: &HeaderHasher, first_nonce: u32, out: &mut [[u8; 32]]) {
+        hash_lanes::<Avx>(header, first_nonce, out);
+    }
+
+    /// `HeaderHasher::hash` for `V::COUNT` nonces at once.
+    #[inline(always)]
+    fn hash_lanes<V: Lanes>(header: &HeaderHasher, first_nonce: u32, out: &mut [[u8; 32]]) {
+        let mut w = [V::splat(0); 64];
+        for (word, value) in w.iter_mut().zip(header.tail) {
+            *word = V::splat(value);
+        }
+        w[NONCE_WORD] = V::nonces(first_nonce);
+        expand(&mut w);
+
+        let mut vars = header.precomputed.map(V::splat);
+        for round in NONCE_WORD..64 {
+            step(&mut vars, w[round].add(V::splat(K[round])));
+        }
+
+        for (word, (var, mid)) in w.iter_mut().zip(vars.iter().zip(header.midstate)) {
+            *word = var.add(V::splat(mid));
+        }
+        w[8] = V::splat(0x8000_0000);
+        for word in &mut w[9..15] {
+            *word = V::splat(0);
+        }
+        w[15] = V::splat(32 * 8);
+        expand(&mut w);
+
+        let mut vars = IV.map(V::splat);
+        for (k, word) in K.iter().zip(w) {
+            step(&mut vars, word.add(V::splat(*k)));
+        }
+
+        let mut lanes = [0u32; 8];
+        for (i, (var, iv)) in vars.iter().zip(IV).enumerate() {
+            var.add(V::splat(iv)).store(&mut lanes);
+            for (digest, value) in out.iter_mut().zip(&lanes[..V::COUNT]) {
+                digest[i * 4..i * 4 + 4].copy_from_slice(&value.to_be_bytes());
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn expand<V: Lanes>(w: &mut [V; 64]) {
+        for i in 16..64 {
+            let s0 =
+                w[i - 15].rotr::<7, 25>().xor(w[i - 15].rotr::<18, 14>()).xor(w[i - 15].shr::<3>());
+            let s1 =
+                w[i - 2].rotr::<17, 15>().xor(w[i - 2].rotr::<19, 13>()).xor(w[i - 2].shr::<10>());
+            w[i] = w[i - 16].add(s0).add(w[i - 7]).add(s1);
+        }
+    }
+
+    #[inline(always)]
+    #[allow(clippy::many_single_char_names)] // FIPS 180-4 names
+    fn step<V: Lanes>(vars: &mut [V; 8], kw: V) {
+        let [a, b, c, d, e, f, g, h] = *vars;
+        let s1 = e.rotr::<6, 26>().xor(e.rotr::<11, 21>()).xor(e.rotr::<25, 7>());
+        let ch = e.and(f).xor(e.andnot(g));
+        let t1 = h.add(s1).add(ch).add(kw);
+        let s0 = a.rotr::<2, 30>().xor(a.rotr::<13, 19>()).xor(a.rotr::<22, 10>());
+        let maj = a.and(b).xor(a.and(c)).xor(b.and(c));
+        *vars = [t1.add(s0.add(maj)), a, b, c, d.add(t1), e, f, g];
+    }
+
+    /// `HeaderHasher::hash` through the SHA extensions.
+    #[target_feature(enable = "sha,ssse3,sse4.1")]
+    pub(super) fn hash_sha_ni(header: &HeaderHasher, nonce: u32) -> [u8; 32] {
+        let mut block = header.tail;
+        block[NONCE_WORD] = nonce.swap_bytes();
+        let mut first = header.midstate;
+        compress_sha_ni(&mut first, &block);
+
+        let mut digest_block = [0u32; 16];
+        digest_block[..8].copy_from_slice(&first);
+        digest_block[8] = 0x8000_0000;
+        digest_block[15] = 32 * 8;
+        let mut state = IV;
+        compress_sha_ni(&mut state, &digest_block);
+
+        let mut digest = [0u8; 32];
+        for (chunk, word) in digest.chunks_exact_mut(4).zip(state) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+
+    /// SHA-256 compression with `sha256rnds2`, four rounds per step.
+    ///
+    /// The round instructions keep the state as `[F, E, B, A]` and
+    /// `[H, G, D, C]` (lane 0 first); `sha256msg1`/`sha256msg2` extend the
+    /// schedule four words at a time.
+    #[target_feature(enable = "sha,ssse3,sse4.1")]
+    #[allow(clippy::many_single_char_names)] // FIPS 180-4 names
+    fn compress_sha_ni(state: &mut [u32; 8], block: &[u32; 16]) {
+        let lanes = |w: [u32; 4]| {
+            _mm_setr_epi32(
+                w[0].cast_signed(),
+                w[1].cast_signed(),
+                w[2].cast_signed(),
+                w[3].cast_signed(),
+            )
+        };
+        let [a, b, c, d, e, f, g, h] = *state;
+        let mut abef = lanes([f, e, b, a]);
+        let mut cdgh = lanes([h, g, d, c]);
+
+        let mut msgs =
+            [0, 4, 8, 12].map(|i| lanes([block[i], block[i + 1], block[i + 2], block[i + 3]]));
+        for group in 0..16 {
+            let current = msgs[group % 4];
+            let k = lanes([K[group * 4], K[group * 4 + 1], K[group * 4 + 2], K[group * 4 + 3]]);
+            let wk = _mm_add_epi32(current, k);
+            cdgh = _mm_sha256rnds2_epu32(cdgh, abef, wk);
+            if (3..15).contains(&group) {
+                // Finish words 4(group + 1).. from the partial sums of msg1.
+                let next = (group + 1) % 4;
+                let carry = _mm_alignr_epi8::<4>(current, msgs[(group + 3) % 4]);
+                msgs[next] = _mm_sha256msg2_epu32(_mm_add_epi32(msgs[next], carry), current);
+            }
+            abef = _mm_sha256rnds2_epu32(abef, cdgh, _mm_shuffle_epi32::<0x0E>(wk));
+            if (1..13).contains(&group) {
+                let previous = (group + 3) % 4;
+                msgs[previous] = _mm_sha256msg1_epu32(msgs[previous], current);
+            }
+        }
+
+        let rounds = [
+            _mm_extract_epi32::<3>(abef),
+            _mm_extract_epi32::<2>(abef),
+            _mm_extract_epi32::<3>(cdgh),
+            _mm_extract_epi32::<2>(cdgh),
+            _mm_extract_epi32::<1>(abef),
+            _mm_extract_epi32::<0>(abef),
+            _mm_extract_epi32::<1>(cdgh),
+            _mm_extract_epi32::<0>(cdgh),
+        ];
+        for (value, round) in state.iter_mut().zip(rounds) {
+            *value = value.wrapping_add(round.cast_unsigned());
+        }
+    }
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+    use crate::{r#impl::double_sha256, types::BlockHeader};
+
+    /// Every backend this CPU can run.
+    fn available_backends() -> Vec<Arc<dyn HashBackend>> {
+        let mut backends: Vec<Arc<dyn HashBackend>> = vec![Arc::new(ScalarBackend)];
+        #[cfg(target_arch = "x86_64")]
+        {
+            if let Some(backend) = Sse41Backend::detect() {
+                backends.push(Arc::new(backend));
+            }
+            if let Some(backend) = Avx2Backend::detect() {
+                backends.push(Arc::new(backend));
+            }
+            if let Some(backend) = ShaNiBackend::detect() {
+                backends.push(Arc::new(backend));
+            }
+        }
+        backends
+    }
+
+    fn header(seed: u32) -> BlockHeader {
+        let mut prev_block_hash = [0u8; 32];
+        let mut merkle_root = [0u8; 32];
+        for i in 0..32u8 {
+            prev_block_hash[usize::from(i)] = i.wrapping_mul(53) ^ seed as u8;
+            merkle_root[usize::from(i)] = i.wrapping_mul(29).wrapping_add((seed >> 8) as u8);
+        }
+        BlockHeader {
+            version: 0x2000_0000 | seed,
+            prev_block_hash,
+            merkle_root,
+            timestamp: 1_700_000_000 + seed % 7200,
+            bits: 0x1703_0ecd,
+            nonce: 0,
+        }
+    }
+
+    #[test]
+    fn test_backends_match_double_sha256() {
+        for backend in available_backends() {
+            let lanes = backend.lanes();
+            assert!(lanes <= MAX_HASH_LANES);
+            for seed in [0, 1, 0xBEEF, 0x0123_4567] {
+                let mut header = header(seed);
+                let hasher = HeaderHasher::new(&header);
+                for first in [0, 7, 0x8000_0000, u32::MAX - 2, seed.wrapping_mul(2_654_435_761)] {
+                    let mut out = [[0u8; 32]; MAX_HASH_LANES];
+                    backend.hash_batch(&hasher, first, &mut out);
+                    for (lane, digest) in (0..lanes as u32).zip(out) {
+                        header.nonce = first.wrapping_add(lane);
+                        assert_eq!(
+                            digest,
+                            double_sha256(&header.serialize()),
+                            "{} lane {lane} nonce {:#x}",
+                            backend.name(),
+                            header.nonce
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_backends_scan_alike() {
+        let hasher = HeaderHasher::new(&header(42));
+        let mut limit = [0u8; 32];
+        limit[0] = 0x04;
+        let target = HashTarget { target: limit };
+
+        let scan = |backend: &dyn HashBackend| {
+            let mut found = Vec::new();
+            let mut nonce = 0u32;
+            while nonce < 4096 {
+                let count = backend.lanes().min(4096 - nonce as usize);
+                backend.scan(&hasher, nonce, count, &target, &mut |n, hash| found.push((n, hash)));
+                nonce += backend.lanes() as u32;
+            }
+            found
+        };
+
+        let expected = scan(&ScalarBackend);
+        assert!(!expected.is_empty());
+        for backend in available_backends() {
+            assert_eq!(scan(backend.as_ref()), expected, "{}", backend.name());
+        }
+    }
+
+    #[test]
+    fn test_select_respects_profile() {
+        let profile = MiningHardwareProfile {
+            has_sha_extensions: false,
+            has_avx2: false,
+            has_sse41: false,
+            ..MiningHardwareProfile::default()
+        };
+        assert_eq!(select_hash_backend(&profile).name(), "scalar");
+
+        let detected = MiningHardwareProfile::default();
+        let backend = select_hash_backend(&detected);
+        let expected = if detected.has_sha_extensions {
+            "sha-ni"
+        } else if detected.has_avx2 {
+            "avx2"
+        } else if detected.has_sse41 {
+            "sse4.1"
+        } else {
+            "scalar"
+        };
+        assert_eq!(backend.name(), expected);
+    }
+}
@@ -418,6 +418,22 @@ impl HashRateMonitor {
         Ok(alerts.clone())
     }
 
+    /// Raises a temperature warning.
+    pub fn temperature_warning(&self, temperature: f64, limit: f64) -> MiningResult<()> {
+        let mut alerts = self.alerts.lock().map_err(|_| {
+            MiningError::Coordinator("Failed to acquire lock on alerts".to_string())
+        })?;
+
+        alerts.push(Alert {
+            timestamp: Instant::now(),
+            alert_type: AlertType::TemperatureWarning,
+            message: format!("CPU temperature {temperature:.1} °C is near the {limit:.0} °C limit"),
+            value: temperature,
+            threshold: limit,
+        });
+        Ok(())
+    }
+
     /// Clears alerts.
     pub fn clear_alerts(&self) -> MiningResult<()> {
         let mut alerts = self.alerts.lock().map_err(|_| {
@@ -538,6 +554,17 @@ mod tests {
         assert_eq!(stats.sample_count, 1);
     }
 
+    #[test]
+    fn test_temperature_warning() {
+        let monitor = HashRateMonitor::new(HashRateMonitorConfig::default());
+        monitor.temperature_warning(78.5, 80.0).unwrap();
+
+        let alerts = monitor.alerts().unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].alert_type, AlertType::TemperatureWarning);
+        assert!((alerts[0].value - 78.5).abs() < 0.01);
+    }
+
     #[test]
     fn test_format_hash_rate() {
         assert_eq!(HashRateMonitor::format_hash_rate(1_500_000.0), "1.50 MH/s");
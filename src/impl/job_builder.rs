@@ -0,0 +1,456 @@
+//! Block header construction from Stratum job templates.
+//!
+//! A `mining.notify` message does not carry a merkle root. The miner builds
+//! the coinbase transaction from the pool's two halves and the extranonces,
+//! hashes it, and folds the merkle branch into it:
+//!
+//! ```text
+//! coinbase    = coinb1 ‖ extranonce1 ‖ extranonce2 ‖ coinb2
+//! root        = SHA256d(coinbase)
+//! root        = SHA256d(root ‖ branch[i])   for each branch hash
+//! ```
+//!
+//! All hashes are kept in internal byte order, which is what the 80-byte
+//! header serializes. Stratum's `prevhash` is the exception: it is sent as
+//! eight 4-byte words with the bytes of each word reversed.
+//!
+//! ## Rolling
+//!
+//! Once a header's nonce space is used up, [`roll_work`] moves to a fresh
+//! header: the version bits inside the job's `version_mask` (BIP320) count
+//! up first; when they wrap around, extranonce 2 counts up as a big-endian
+//! number and the merkle root is rebuilt; when extranonce 2 wraps around,
+//! ntime steps forward by one second, up to the job's `max_ntime`.
+//! [`Nonce::increment_or_roll`] does the same when a single nonce counter
+//! overflows.
+
+use crate::{
+    r#impl::double_sha256,
+    types::{BlockHeader, CoinbaseParts, HashTarget, MiningJob, Nonce},
+};
+
+/// How far past the job's ntime a header timestamp may be rolled.
+///
+/// Pools commonly reject shares more than about two hours ahead of the job.
+pub const MAX_NTIME_ROLL: u32 = 7000;
+
+/// Decoded `mining.notify` job.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobTemplate {
+    /// Pool job identifier.
+    pub job_id:        String,
+    /// Previous block hash in internal (header) byte order.
+    pub prev_hash:     [u8; 32],
+    /// Coinbase transaction bytes before the extranonces.
+    pub coinbase1:     Vec<u8>,
+    /// Coinbase transaction bytes after the extranonces.
+    pub coinbase2:     Vec<u8>,
+    /// Merkle branch hashes in internal byte order.
+    pub merkle_branch: Vec<[u8; 32]>,
+    /// Block version.
+    pub version:       u32,
+    /// Encoded network difficulty.
+    pub nbits:         u32,
+    /// Block timestamp.
+    pub ntime:         u32,
+    /// Whether previous jobs must be abandoned.
+    pub clean_jobs:    bool,
+}
+
+impl JobTemplate {
+    /// Assemble the coinbase transaction for the given extranonces.
+    #[must_use]
+    pub fn coinbase(&self, extranonce1: &[u8], extranonce2: &[u8]) -> Vec<u8> {
+        build_coinbase(&self.coinbase1, extranonce1, extranonce2, &self.coinbase2)
+    }
+
+    /// Compute the merkle root for the given extranonces.
+    #[must_use]
+    pub fn merkle_root(&self, extranonce1: &[u8], extranonce2: &[u8]) -> [u8; 32] {
+        merkle_root(double_sha256(&self.coinbase(extranonce1, extranonce2)), &self.merkle_branch)
+    }
+
+    /// Build the block header for the given extranonces with a zero nonce.
+    #[must_use]
+    pub fn header(&self, extranonce1: &[u8], extranonce2: &[u8]) -> BlockHeader {
+        BlockHeader {
+            version:         self.version,
+            prev_block_hash: self.prev_hash,
+            merkle_root:     self.merkle_root(extranonce1, extranonce2),
+            timestamp:       self.ntime,
+            bits:            self.nbits,
+            nonce:           0,
+        }
+    }
+
+    /// Build a mining job for the given extranonces and share target.
+    #[must_use]
+    pub fn to_job(
+        &self, extranonce1: &[u8], extranonce2: Vec<u8>, target: HashTarget,
+    ) -> MiningJob {
+        MiningJob {
+            job_id: self.job_id.clone(),
+            header: self.header(extranonce1, &extranonce2),
+            target,
+            extranonce1: extranonce1.to_vec(),
+            extranonce2_size: extranonce2.len(),
+            extranonce2,
+            clean_jobs: self.clean_jobs,
+            version_mask: 0,
+            coinbase: Some(CoinbaseParts {
+                coinbase1:     self.coinbase1.clone(),
+                coinbase2:     self.coinbase2.clone(),
+                merkle_branch: self.merkle_branch.clone(),
+            }),
+            max_ntime: self.ntime.saturating_add(MAX_NTIME_ROLL),
+            algorithm: None,
+        }
+    }
+}
+
+/// Move `header` to the next unexplored version, extranonce 2 and ntime of
+/// `job`.
+///
+/// `extranonce2` is the value `header` was built with and is updated in
+/// place. Returns `false` once all three are exhausted; `header` is then
+/// left unchanged.
+pub fn roll_work(job: &MiningJob, extranonce2: &mut [u8], header: &mut BlockHeader) -> bool {
+    let version = header.version;
+    if roll_version(job, header) {
+        return true;
+    }
+    if roll_extranonce2(job, extranonce2, header) {
+        return true;
+    }
+    header.version = version;
+    false
+}
+
+impl Nonce {
+    /// Increment the nonce of `header`, rolling to fresh work of `job` on
+    /// overflow.
+    ///
+    /// When the nonce wraps, [`roll_work`] moves `header` and `extranonce2`
+    /// on and the nonce starts again from zero. Returns `None`, leaving the
+    /// nonce at `u32::MAX`, once the job's work is exhausted.
+    pub fn increment_or_roll(
+        &mut self, job: &MiningJob, extranonce2: &mut [u8], header: &mut BlockHeader,
+    ) -> Option<u32> {
+        match self.increment() {
+            Some(nonce) => header.nonce = nonce,
+            None if roll_work(job, extranonce2, header) => {
+                self.0 = 0;
+                header.nonce = 0;
+            },
+            None => return None,
+        }
+        Some(self.0)
+    }
+}
+
+/// Count up the version bits inside the job's mask; `false` when they wrap
+/// back to the job's own bits.
+fn roll_version(job: &MiningJob, header: &mut BlockHeader) -> bool {
+    let bits = (header.version | !job.version_mask).wrapping_add(1) & job.version_mask;
+    header.version = job.rolled_version(bits);
+    bits != job.header.version & job.version_mask
+}
+
+fn roll_extranonce2(job: &MiningJob, extranonce2: &mut [u8], header: &mut BlockHeader) -> bool {
+    let Some(ref coinbase) = job.coinbase else {
+        return roll_ntime(job, header);
+    };
+
+    let previous = extranonce2.to_vec();
+    let wrapped = extranonce2.iter_mut().rev().all(|byte| {
+        *byte = byte.wrapping_add(1);
+        *byte == 0
+    });
+    if wrapped && !roll_ntime(job, header) {
+        extranonce2.copy_from_slice(&previous);
+        return false;
+    }
+
+    let coinbase_tx =
+        build_coinbase(&coinbase.coinbase1, &job.extranonce1, extranonce2, &coinbase.coinbase2);
+    header.merkle_root = merkle_root(double_sha256(&coinbase_tx), &coinbase.merkle_branch);
+    true
+}
+
+fn roll_ntime(job: &MiningJob, header: &mut BlockHeader) -> bool {
+    if header.timestamp >= job.max_ntime {
+        return false;
+    }
+    header.timestamp += 1;
+    true
+}
+
+/// Concatenate `coinbase1 ‖ extranonce1 ‖ extranonce2 ‖ coinbase2`.
+#[must_use]
+pub fn build_coinbase(
+    coinbase1: &[u8], extranonce1: &[u8], extranonce2: &[u8], coinbase2: &[u8],
+) -> Vec<u8> {
+    let mut coinbase = Vec::with_capacity(
+        coinbase1.len() + extranonce1.len() + extranonce2.len() + coinbase2.len(),
+    );
+    coinbase.extend_from_slice(coinbase1);
+    coinbase.extend_from_slice(extranonce1);
+    coinbase.extend_from_slice(extranonce2);
+    coinbase.extend_from_slice(coinbase2);
+    coinbase
+}
+
+/// Fold a Stratum merkle branch into the coinbase hash.
+#[must_use]
+pub fn merkle_root(coinbase_hash: [u8; 32], merkle_branch: &[[u8; 32]]) -> [u8; 32] {
+    let mut pair = [0u8; 64];
+    merkle_branch.iter().fold(coinbase_hash, |root, branch| {
+        pair[..32].copy_from_slice(&root);
+        pair[32..].copy_from_slice(branch);
+        double_sha256(&pair)
+    })
+}
+
+/// Convert Stratum's word-swapped `prevhash` into header byte order.
+///
+/// The conversion is its own inverse.
+#[must_use]
+pub fn prev_hash_from_stratum(mut prev_hash: [u8; 32]) -> [u8; 32] {
+    for word in prev_hash.chunks_exact_mut(4) {
+        word.reverse();
+    }
+    prev_hash
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).expect("test assertion"))
+            .collect()
+    }
+
+    fn hash(s: &str) -> [u8; 32] {
+        hex(s).try_into().expect("test assertion")
+    }
+
+    /// Hash in display (RPC/explorer) byte order to internal order.
+    fn display_hash(s: &str) -> [u8; 32] {
+        let mut h = hash(s);
+        h.reverse();
+        h
+    }
+
+    /// Header hash in display byte order.
+    fn block_hash(header: &BlockHeader) -> [u8; 32] {
+        let mut h = double_sha256(&header.serialize());
+        h.reverse();
+        h
+    }
+
+    /// Mainnet block 0 coinbase, with 9 bytes of its scriptSig text
+    /// standing in for the extranonces.
+    const GENESIS_COINBASE1: &str = concat!(
+        "01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff",
+        "4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039",
+    );
+    const GENESIS_EXTRANONCE1: &str = "204368616e";
+    const GENESIS_EXTRANONCE2: &str = "63656c6c";
+    const GENESIS_COINBASE2: &str = concat!(
+        "6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff",
+        "0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61de",
+        "b649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000",
+    );
+
+    fn genesis_template() -> JobTemplate {
+        JobTemplate {
+            job_id:        "0".into(),
+            prev_hash:     [0; 32],
+            coinbase1:     hex(GENESIS_COINBASE1),
+            coinbase2:     hex(GENESIS_COINBASE2),
+            merkle_branch: Vec::new(),
+            version:       1,
+            nbits:         0x1d00ffff,
+            ntime:         1231006505,
+            clean_jobs:    true,
+        }
+    }
+
+    #[test]
+    fn test_genesis_coinbase_and_merkle_root() {
+        let template = genesis_template();
+        let en1 = hex(GENESIS_EXTRANONCE1);
+        let en2 = hex(GENESIS_EXTRANONCE2);
+
+        let coinbase = template.coinbase(&en1, &en2);
+        assert_eq!(coinbase.len(), 204);
+        assert_eq!(
+            template.merkle_root(&en1, &en2),
+            display_hash("4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b")
+        );
+    }
+
+    #[test]
+    fn test_genesis_header_hash() {
+        let template = genesis_template();
+        let mut header = template.header(&hex(GENESIS_EXTRANONCE1), &hex(GENESIS_EXTRANONCE2));
+        header.nonce = 2083236893;
+
+        assert_eq!(
+            block_hash(&header),
+            hash("000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f")
+        );
+    }
+
+    #[test]
+    fn test_block_170_merkle_branch() {
+        // Block 170: coinbase plus the first person-to-person transaction.
+        let coinbase_txid =
+            display_hash("b1fea52486ce0c62bb442b530a3f0132b826c74e473d1f2c220bfa78111c5082");
+        let branch =
+            [display_hash("f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16")];
+
+        assert_eq!(
+            merkle_root(coinbase_txid, &branch),
+            display_hash("7dac2c5666815c17a3b36427de37bb9d2e2c5ccec3f8633eb91a4205cb4c10ff")
+        );
+    }
+
+    #[test]
+    fn test_block_170_header_from_stratum_prevhash() {
+        // prevhash as a pool would send it in mining.notify.
+        let stratum_prev_hash =
+            hash("0a84bd55d08a7978683f85da183d4f97dbd12b3e1f2c846a2a22cfee00000000");
+        let prev_hash = prev_hash_from_stratum(stratum_prev_hash);
+        assert_eq!(
+            prev_hash,
+            display_hash("000000002a22cfee1f2c846adbd12b3e183d4f97683f85dad08a79780a84bd55")
+        );
+        assert_eq!(prev_hash_from_stratum(prev_hash), stratum_prev_hash);
+
+        let header = BlockHeader {
+            version:         1,
+            prev_block_hash: prev_hash,
+            merkle_root:     display_hash(
+                "7dac2c5666815c17a3b36427de37bb9d2e2c5ccec3f8633eb91a4205cb4c10ff",
+            ),
+            timestamp:       1231731025,
+            bits:            0x1d00ffff,
+            nonce:           1889418792,
+        };
+        assert_eq!(
+            block_hash(&header),
+            hash("00000000d1145790a8694403d4063f323d499e655c83426834d4ce2f8dd4a2ee")
+        );
+    }
+
+    #[test]
+    fn test_to_job_carries_extranonces() {
+        let template = genesis_template();
+        let en1 = hex(GENESIS_EXTRANONCE1);
+        let job = template.to_job(&en1, hex(GENESIS_EXTRANONCE2), HashTarget::from_difficulty(1.0));
+
+        assert_eq!(job.job_id, "0");
+        assert_eq!(job.extranonce1, en1);
+        assert_eq!(job.extranonce2_size, 4);
+        assert_eq!(job.header.merkle_root, template.merkle_root(&en1, &job.extranonce2));
+        assert!(job.clean_jobs);
+        assert_eq!(job.max_ntime, template.ntime + MAX_NTIME_ROLL);
+    }
+
+    #[test]
+    fn test_roll_work_extranonce2_then_ntime() {
+        let template = genesis_template();
+        let en1 = hex(GENESIS_EXTRANONCE1);
+        let mut job = template.to_job(&en1, vec![0xFF, 0xFE], HashTarget::from_difficulty(1.0));
+        job.max_ntime = template.ntime + 1;
+        let mut en2 = job.extranonce2.clone();
+        let mut header = job.header.clone();
+
+        assert!(roll_work(&job, &mut en2, &mut header));
+        assert_eq!(en2, [0xFF, 0xFF]);
+        assert_eq!(header.timestamp, template.ntime);
+        assert_eq!(header.merkle_root, template.merkle_root(&en1, &en2));
+
+        assert!(roll_work(&job, &mut en2, &mut header));
+        assert_eq!(en2, [0x00, 0x00]);
+        assert_eq!(header.timestamp, template.ntime + 1);
+        assert_eq!(header.merkle_root, template.merkle_root(&en1, &en2));
+
+        en2 = vec![0xFF, 0xFF];
+        let before = header.clone();
+        assert!(!roll_work(&job, &mut en2, &mut header));
+        assert_eq!(en2, [0xFF, 0xFF]);
+        assert_eq!(header.serialize(), before.serialize());
+    }
+
+    #[test]
+    fn test_roll_work_version_before_extranonce2() {
+        let template = genesis_template();
+        let en1 = hex(GENESIS_EXTRANONCE1);
+        let mut job = template.to_job(&en1, vec![0xFF, 0xFF], HashTarget::from_difficulty(1.0));
+        job.header.version = 0x2000_2000;
+        job.version_mask = 0x0000_6000;
+        let mut en2 = job.extranonce2.clone();
+        let mut header = job.header.clone();
+
+        let mut versions = vec![header.version];
+        while roll_work(&job, &mut en2, &mut header) && en2 == job.extranonce2 {
+            versions.push(header.version);
+        }
+        assert_eq!(versions, [0x2000_2000, 0x2000_4000, 0x2000_6000, 0x2000_0000]);
+        assert_eq!(header.version, job.header.version);
+        assert_eq!(en2, [0x00, 0x00]);
+
+        // Exhausted work leaves the rolled version alone.
+        job.max_ntime = header.timestamp;
+        en2 = vec![0xFF, 0xFF];
+        header.version = 0x2000_0000;
+        assert!(!roll_work(&job, &mut en2, &mut header));
+        assert_eq!(header.version, 0x2000_0000);
+    }
+
+    #[test]
+    fn test_nonce_overflow_rolls_work() {
+        let template = genesis_template();
+        let en1 = hex(GENESIS_EXTRANONCE1);
+        let mut job = template.to_job(&en1, vec![0xFF, 0xFE], HashTarget::from_difficulty(1.0));
+        job.max_ntime = template.ntime;
+        let mut en2 = job.extranonce2.clone();
+        let mut header = job.header.clone();
+        let mut nonce = Nonce(u32::MAX - 1);
+
+        assert_eq!(nonce.increment_or_roll(&job, &mut en2, &mut header), Some(u32::MAX));
+        assert_eq!(header.nonce, u32::MAX);
+        assert_eq!(en2, job.extranonce2);
+
+        assert_eq!(nonce.increment_or_roll(&job, &mut en2, &mut header), Some(0));
+        assert_eq!(header.nonce, 0);
+        assert_eq!(en2, [0xFF, 0xFF]);
+        assert_eq!(header.merkle_root, template.merkle_root(&en1, &en2));
+
+        nonce = Nonce(u32::MAX);
+        assert_eq!(nonce.increment_or_roll(&job, &mut en2, &mut header), None);
+        assert_eq!(nonce.0, u32::MAX);
+        assert_eq!(en2, [0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn test_roll_work_without_coinbase_rolls_ntime() {
+        let template = genesis_template();
+        let mut job = template.to_job(&[], vec![0; 4], HashTarget::from_difficulty(1.0));
+        job.coinbase = None;
+        job.max_ntime = template.ntime + 1;
+        let mut en2 = job.extranonce2.clone();
+        let mut header = job.header.clone();
+
+        assert!(roll_work(&job, &mut en2, &mut header));
+        assert_eq!(en2, [0; 4]);
+        assert_eq!(header.timestamp, template.ntime + 1);
+        assert_eq!(header.merkle_root, job.header.merkle_root);
+        assert!(!roll_work(&job, &mut en2, &mut header));
+    }
+}
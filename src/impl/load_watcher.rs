@@ -0,0 +1,402 @@
+//! Pausing mining while other work keeps the system busy.
+//!
+//! [`LoadWatcher::update`] reads `/proc/stat` and `/proc/loadavg` and
+//! measures how busy the machine is with work other than our own mining
+//! threads:
+//!
+//! - CPU use: busy jiffies across all CPUs since the last update, minus the
+//!   jiffies of our `mining-worker-*` threads from `/proc/self/task`, as a
+//!   share of all CPU time.
+//! - Run queue: the one-minute load average minus our workers' part of it,
+//!   per CPU. The kernel decays that average over a minute, so the watcher
+//!   keeps a matching average of its workers' load (running workers times
+//!   their duty cycle); right after a pause it still subtracts what they
+//!   added before.
+//!
+//! The larger of the two is the pressure. Mining pauses as soon as it
+//! reaches [`LoadPolicy::pause_above`], and resumes once it has stayed below
+//! [`LoadPolicy::resume_below`] for [`LoadPolicy::resume_after`].
+//!
+//! The watcher is poll-driven like the rest of the plugin: the host calls
+//! [`MiningPlugin::check_system_load`] every few seconds.
+//!
+//! [`MiningPlugin::check_system_load`]: crate::r#impl::MiningPlugin::check_system_load
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::{
+    errors::{MiningError, MiningResult},
+    types::{PauseEvent, PauseReason},
+};
+
+/// Thread name prefix of mining workers, as the kernel reports it.
+const WORKER_COMM_PREFIX: &str = "mining-worker";
+
+/// Time constant of the kernel's one-minute load average.
+const LOADAVG_PERIOD: Duration = Duration::from_secs(60);
+
+/// When to pause and resume mining for system load.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadPolicy {
+    /// Pause when other work reaches this share of the machine (0.0-1.0).
+    pub pause_above:  f64,
+    /// Resume only while other work stays below this share.
+    pub resume_below: f64,
+    /// How long the load must stay low before resuming.
+    pub resume_after: Duration,
+}
+
+impl Default for LoadPolicy {
+    fn default() -> Self {
+        Self { pause_above: 0.5, resume_below: 0.25, resume_after: Duration::from_secs(30) }
+    }
+}
+
+/// System load not caused by our mining threads.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoadSample {
+    /// Share of all CPU time used by other work since the last sample.
+    pub other_cpu:  f64,
+    /// One-minute load average of other work, per CPU.
+    pub other_load: f64,
+}
+
+impl LoadSample {
+    /// The higher of the two readings.
+    #[must_use]
+    pub fn pressure(&self) -> f64 {
+        self.other_cpu.max(self.other_load)
+    }
+}
+
+/// Cumulative CPU counters, in clock ticks.
+#[derive(Debug, Clone, Copy)]
+struct CpuTimes {
+    total:   u64,
+    busy:    u64,
+    workers: u64,
+}
+
+/// Watches system load and decides when mining should pause.
+#[derive(Debug, Clone)]
+pub struct LoadWatcher {
+    proc_root:   PathBuf,
+    policy:      LoadPolicy,
+    previous:    Option<(CpuTimes, Instant)>,
+    /// Our workers' share of the load average, decayed like the kernel's.
+    own_load:    f64,
+    paused:      bool,
+    quiet_since: Option<Instant>,
+}
+
+impl LoadWatcher {
+    /// Create a watcher reading `/proc`.
+    #[must_use]
+    pub fn new(policy: LoadPolicy) -> Self {
+        Self {
+            proc_root: PathBuf::from("/proc"),
+            policy,
+            previous: None,
+            own_load: 0.0,
+            paused: false,
+            quiet_since: None,
+        }
+    }
+
+    /// Read `stat`, `loadavg` and `self/task` under `root` instead of
+    /// `/proc`.
+    #[must_use]
+    pub fn with_proc_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.proc_root = root.into();
+        self
+    }
+
+    /// Whether the watcher currently wants mining paused.
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Forget earlier samples and any pause, e.g. after mining stopped.
+    pub fn reset(&mut self) {
+        self.previous = None;
+        self.own_load = 0.0;
+        self.paused = false;
+        self.quiet_since = None;
+    }
+
+    /// Measure load since the last sample, given the load our workers put
+    /// on the run queue: running workers times their duty cycle.
+    ///
+    /// Returns `None` on the first call, which only records the counters.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MiningError::HardwareDetection` if the proc files cannot be
+    /// read or parsed.
+    pub fn sample(&mut self, worker_load: f64) -> MiningResult<Option<LoadSample>> {
+        self.sample_at(worker_load, Instant::now())
+    }
+
+    fn sample_at(&mut self, worker_load: f64, now: Instant) -> MiningResult<Option<LoadSample>> {
+        let stat = read(&self.proc_root.join("stat"))?;
+        let (total, busy, cpus) = parse_stat(&stat)?;
+        let workers = worker_ticks(&self.proc_root.join("self/task"));
+        let current = CpuTimes { total, busy, workers };
+        let Some((previous, sampled_at)) = self.previous.replace((current, now)) else {
+            self.own_load = worker_load;
+            return Ok(None);
+        };
+
+        let total = current.total.saturating_sub(previous.total);
+        let other = current
+            .busy
+            .saturating_sub(previous.busy)
+            .saturating_sub(current.workers.saturating_sub(previous.workers));
+        let other_cpu = if total == 0 { 0.0 } else { (other as f64 / total as f64).min(1.0) };
+
+        let elapsed = now.duration_since(sampled_at).as_secs_f64();
+        let decay = (-elapsed / LOADAVG_PERIOD.as_secs_f64()).exp();
+        self.own_load = self.own_load * decay + worker_load * (1.0 - decay);
+        let load = parse_loadavg(&read(&self.proc_root.join("loadavg"))?)?;
+        let other_load = (load - self.own_load).max(0.0) / cpus.max(1) as f64;
+
+        Ok(Some(LoadSample { other_cpu, other_load }))
+    }
+
+    /// Sample the load and return a pause or resume event if mining should
+    /// change state.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MiningError::HardwareDetection` if the proc files cannot be
+    /// read or parsed.
+    pub fn update(&mut self, worker_load: f64) -> MiningResult<Option<PauseEvent>> {
+        let sample = self.sample(worker_load)?;
+        Ok(sample.and_then(|sample| self.decide(sample, Instant::now())))
+    }
+
+    /// Apply the pause/resume hysteresis to `sample` taken at `now`.
+    fn decide(&mut self, sample: LoadSample, now: Instant) -> Option<PauseEvent> {
+        let pressure = sample.pressure();
+        if !self.paused {
+            if pressure < self.policy.pause_above {
+                return None;
+            }
+            self.paused = true;
+            self.quiet_since = None;
+        } else if pressure >= self.policy.resume_below {
+            self.quiet_since = None;
+            return None;
+        } else {
+            let quiet_since = *self.quiet_since.get_or_insert(now);
+            if now.duration_since(quiet_since) < self.policy.resume_after {
+                return None;
+            }
+            self.paused = false;
+            self.quiet_since = None;
+        }
+
+        Some(PauseEvent {
+            paused:  self.paused,
+            reason:  PauseReason::SystemLoad,
+            at:      SystemTime::now(),
+            reading: pressure,
+        })
+    }
+}
+
+fn read(path: &Path) -> MiningResult<String> {
+    fs::read_to_string(path).map_err(|e| {
+        MiningError::HardwareDetection(format!("Failed to read {}: {e}", path.display()))
+    })
+}
+
+/// Total and busy ticks of the aggregate `cpu` line, and the CPU count.
+fn parse_stat(stat: &str) -> MiningResult<(u64, u64, usize)> {
+    let invalid = || MiningError::HardwareDetection("Invalid /proc/stat".into());
+    let line = stat.lines().find(|line| line.starts_with("cpu ")).ok_or_else(invalid)?;
+    let fields = line
+        .split_whitespace()
+        .skip(1)
+        .map(str::parse::<u64>)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| invalid())?;
+    if fields.len() < 4 {
+        return Err(invalid());
+    }
+
+    // user nice system idle iowait irq softirq steal; guest time is already
+    // counted in user and nice.
+    let total: u64 = fields.iter().take(8).sum();
+    let idle = fields[3] + fields.get(4).copied().unwrap_or(0);
+    let cpus = stat
+        .lines()
+        .filter(|line| {
+            line.starts_with("cpu") && line.as_bytes().get(3).is_some_and(u8::is_ascii_digit)
+        })
+        .count();
+    Ok((total, total - idle, cpus))
+}
+
+/// One-minute load average.
+fn parse_loadavg(loadavg: &str) -> MiningResult<f64> {
+    loadavg
+        .split_whitespace()
+        .next()
+        .and_then(|load| load.parse().ok())
+        .ok_or_else(|| MiningError::HardwareDetection("Invalid /proc/loadavg".into()))
+}
+
+/// User plus system ticks of our mining threads.
+fn worker_ticks(tasks: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(tasks) else {
+        return 0;
+    };
+    entries
+        .filter_map(|entry| fs::read_to_string(entry.ok()?.path().join("stat")).ok())
+        .filter_map(|stat| task_ticks(&stat))
+        .sum()
+}
+
+/// `utime + stime` of a `/proc/<pid>/task/<tid>/stat` line if the task is a
+/// mining worker.
+fn task_ticks(stat: &str) -> Option<u64> {
+    // The command name may contain spaces and parentheses; it ends at the
+    // last ')'.
+    let (head, rest) = stat.rsplit_once(')')?;
+    let (_, comm) = head.split_once('(')?;
+    if !comm.starts_with(WORKER_COMM_PREFIX) {
+        return None;
+    }
+    // `rest` starts at field 3 (state); utime and stime are fields 14 and 15.
+    let mut fields = rest.split_whitespace().skip(11);
+    let utime: u64 = fields.next()?.parse().ok()?;
+    let stime: u64 = fields.next()?.parse().ok()?;
+    Some(utime + stime)
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+    use crate::r#impl::test_tree::TempTree;
+
+    /// Fake `/proc` with two CPUs and one mining worker.
+    struct FakeProc(TempTree);
+
+    impl FakeProc {
+        fn new() -> Self {
+            Self(TempTree::new("load"))
+        }
+
+        /// Write counters: `busy` ticks of other work, `worker` ticks of our
+        /// worker and `total` ticks overall.
+        fn write(&self, total: u64, busy: u64, worker: u64, loadavg: f64) {
+            let idle = total - busy - worker;
+            let user = busy + worker;
+            self.0.write(
+                "stat",
+                format!(
+                    "cpu  {user} 0 0 {idle} 0 0 0 0 0 0\ncpu0 0 0 0 0 0 0 0 0 0 0\n\
+                     cpu1 0 0 0 0 0 0 0 0 0 0\nintr 1 2 3\n"
+                ),
+            );
+            self.0.write("loadavg", format!("{loadavg:.2} 0.50 0.40 2/300 4243\n"));
+            self.0.write(
+                "self/task/4242/stat",
+                format!("4242 (mining-worker-0) R 1 1 1 0 -1 0 0 0 0 0 {worker} 0 0 0 39 19 1 0"),
+            );
+            self.0.write(
+                "self/task/4243/stat",
+                "4243 (ui (main)) S 1 1 1 0 -1 0 0 0 0 0 500 500 0 0 20 0 1 0",
+            );
+        }
+    }
+
+    #[test]
+    fn test_sample_excludes_own_workers() {
+        let proc = FakeProc::new();
+        let mut watcher = LoadWatcher::new(LoadPolicy::default()).with_proc_root(proc.0.path());
+        proc.write(1000, 100, 200, 1.0);
+        assert_eq!(watcher.sample(1.0).expect("test assertion"), None);
+
+        // 200 ticks: 50 other work, 100 our worker, 50 idle.
+        proc.write(1200, 150, 300, 2.0);
+        let sample = watcher.sample(1.0).expect("test assertion").expect("test assertion");
+        assert!((sample.other_cpu - 0.25).abs() < 1e-9);
+        assert!((sample.other_load - 0.5).abs() < 1e-9);
+        assert!((sample.pressure() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_load_average_of_just_paused_workers_is_not_other_load() {
+        let proc = FakeProc::new();
+        let mut watcher = LoadWatcher::new(LoadPolicy::default()).with_proc_root(proc.0.path());
+        let start = Instant::now();
+        // Two workers at half duty have kept the load average at 1.0.
+        proc.write(1000, 0, 0, 1.0);
+        assert_eq!(watcher.sample_at(1.0, start).expect("test assertion"), None);
+
+        // Five seconds after pausing the kernel has decayed it to 0.92.
+        proc.write(1200, 0, 0, 0.92);
+        let sample = watcher
+            .sample_at(0.0, start + Duration::from_secs(5))
+            .expect("test assertion")
+            .expect("test assertion");
+        assert!(sample.other_load < 0.01, "other load {}", sample.other_load);
+
+        // Other work showing up while paused still counts.
+        proc.write(1400, 0, 0, 2.84);
+        let sample = watcher
+            .sample_at(0.0, start + Duration::from_secs(10))
+            .expect("test assertion")
+            .expect("test assertion");
+        assert!((sample.other_load - 1.0).abs() < 0.01, "other load {}", sample.other_load);
+    }
+
+    #[test]
+    fn test_pause_and_resume_with_hysteresis() {
+        let mut watcher = LoadWatcher::new(LoadPolicy {
+            pause_above:  0.5,
+            resume_below: 0.25,
+            resume_after: Duration::from_secs(30),
+        });
+        let load = |other_cpu| LoadSample { other_cpu, other_load: 0.0 };
+        let start = Instant::now();
+
+        assert_eq!(watcher.decide(load(0.4), start), None);
+        let paused = watcher.decide(load(0.8), start).expect("test assertion");
+        assert!(paused.paused);
+        assert_eq!(paused.reason, PauseReason::SystemLoad);
+        assert!(watcher.is_paused());
+
+        // Between the thresholds: stays paused, no quiet period started.
+        assert_eq!(watcher.decide(load(0.3), start + Duration::from_secs(1)), None);
+        assert_eq!(watcher.decide(load(0.1), start + Duration::from_secs(2)), None);
+        // A spike restarts the quiet period.
+        assert_eq!(watcher.decide(load(0.6), start + Duration::from_secs(20)), None);
+        assert_eq!(watcher.decide(load(0.1), start + Duration::from_secs(21)), None);
+        assert_eq!(watcher.decide(load(0.1), start + Duration::from_secs(50)), None);
+
+        let resumed =
+            watcher.decide(load(0.1), start + Duration::from_secs(51)).expect("test assertion");
+        assert!(!resumed.paused);
+        assert!(!watcher.is_paused());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_stat("intr 1 2 3\n").is_err());
+        assert!(parse_stat("cpu  1 x 3 4\n").is_err());
+        assert!(parse_loadavg("").is_err());
+        assert_eq!(task_ticks("1 (bash) S 1 1 1 0 -1 0 0 0 0 0 5 5 0"), None);
+        assert_eq!(task_ticks("1 (mining-worker-3) R 1 1 1 0 -1 0 0 0 0 0 5 7 0"), Some(12));
+
+        let mut watcher = LoadWatcher::new(LoadPolicy::default()).with_proc_root("/nonexistent");
+        assert!(matches!(watcher.update(0.0), Err(MiningError::HardwareDetection(_))));
+    }
+}
@@ -0,0 +1,346 @@
+//! SHA-256d of block headers with a precomputed midstate.
+//!
+//! An 80-byte header spans two SHA-256 blocks, and the nonce only appears in
+//! the second one. [`HeaderHasher`] compresses the first block once per
+//! header, so each nonce costs two compressions instead of three:
+//!
+//! ```text
+//! block 1: version ‖ prev_hash ‖ merkle_root[..28]        → midstate (once)
+//! block 2: merkle_root[28..] ‖ ntime ‖ nbits ‖ nonce ‖ pad → first digest
+//! block 3: first digest ‖ pad                              → header hash
+//! ```
+//!
+//! The first three rounds of block 2 only read words that do not involve the
+//! nonce and are precomputed as well. [`HeaderHasher::check`] exits after
+//! round 61 of block 3: that round fixes the digest's last word, which is the
+//! most significant word of the hash when read as a number, and for any
+//! share target that word alone rejects nearly every nonce.
+
+use crate::types::{BlockHeader, HashTarget};
+
+pub(crate) const K: [u32; 64] = [
+    0x428a_2f98,
+    0x7137_4491,
+    0xb5c0_fbcf,
+    0xe9b5_dba5,
+    0x3956_c25b,
+    0x59f1_11f1,
+    0x923f_82a4,
+    0xab1c_5ed5,
+    0xd807_aa98,
+    0x1283_5b01,
+    0x2431_85be,
+    0x550c_7dc3,
+    0x72be_5d74,
+    0x80de_b1fe,
+    0x9bdc_06a7,
+    0xc19b_f174,
+    0xe49b_69c1,
+    0xefbe_4786,
+    0x0fc1_9dc6,
+    0x240c_a1cc,
+    0x2de9_2c6f,
+    0x4a74_84aa,
+    0x5cb0_a9dc,
+    0x76f9_88da,
+    0x983e_5152,
+    0xa831_c66d,
+    0xb003_27c8,
+    0xbf59_7fc7,
+    0xc6e0_0bf3,
+    0xd5a7_9147,
+    0x06ca_6351,
+    0x1429_2967,
+    0x27b7_0a85,
+    0x2e1b_2138,
+    0x4d2c_6dfc,
+    0x5338_0d13,
+    0x650a_7354,
+    0x766a_0abb,
+    0x81c2_c92e,
+    0x9272_2c85,
+    0xa2bf_e8a1,
+    0xa81a_664b,
+    0xc24b_8b70,
+    0xc76c_51a3,
+    0xd192_e819,
+    0xd699_0624,
+    0xf40e_3585,
+    0x106a_a070,
+    0x19a4_c116,
+    0x1e37_6c08,
+    0x2748_774c,
+    0x34b0_bcb5,
+    0x391c_0cb3,
+    0x4ed8_aa4a,
+    0x5b9c_ca4f,
+    0x682e_6ff3,
+    0x748f_82ee,
+    0x78a5_636f,
+    0x84c8_7814,
+    0x8cc7_0208,
+    0x90be_fffa,
+    0xa450_6ceb,
+    0xbef9_a3f7,
+    0xc671_78f2,
+];
+
+pub(crate) const IV: [u32; 8] = [
+    0x6a09_e667,
+    0xbb67_ae85,
+    0x3c6e_f372,
+    0xa54f_f53a,
+    0x510e_527f,
+    0x9b05_688c,
+    0x1f83_d9ab,
+    0x5be0_cd19,
+];
+
+/// Word index of the nonce in the second header block.
+pub(crate) const NONCE_WORD: usize = 3;
+
+/// Rounds of block 3 after which the digest's last word is known.
+const EARLY_EXIT_ROUNDS: usize = 61;
+
+/// Double SHA-256 of one header template over all nonces.
+#[derive(Debug, Clone)]
+pub struct HeaderHasher {
+    /// Chaining value after the first block.
+    pub(crate) midstate:    [u32; 8],
+    /// Working variables after the nonce-independent rounds of block 2.
+    pub(crate) precomputed: [u32; 8],
+    /// Message words of block 2, nonce left as zero.
+    pub(crate) tail:        [u32; 16],
+}
+
+impl HeaderHasher {
+    /// Precompute the midstate for `header`; its nonce is ignored.
+    #[must_use]
+    pub fn new(header: &BlockHeader) -> Self {
+        let bytes = header.serialize();
+        let mut first = [0u32; 16];
+        for (word, chunk) in first.iter_mut().zip(bytes[..64].chunks_exact(4)) {
+            *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        let mut midstate = IV;
+        compress(&mut midstate, &first);
+
+        let mut tail = [0u32; 16];
+        for (word, chunk) in tail.iter_mut().zip(bytes[64..76].chunks_exact(4)) {
+            *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        tail[4] = 0x8000_0000;
+        tail[15] = 80 * 8;
+
+        let mut precomputed = midstate;
+        for (round, word) in tail.iter().enumerate().take(NONCE_WORD) {
+            step(&mut precomputed, K[round].wrapping_add(*word));
+        }
+
+        Self { midstate, precomputed, tail }
+    }
+
+    /// `double_sha256` of the header with `nonce`, in digest byte order.
+    #[must_use]
+    pub fn hash(&self, nonce: u32) -> [u8; 32] {
+        let mut state = IV;
+        compress(&mut state, &self.second_digest_block(nonce));
+        to_bytes(&state)
+    }
+
+    /// Header hash for `nonce`, most significant byte first, if it meets
+    /// `target`.
+    #[must_use]
+    pub fn check(&self, nonce: u32, target: &HashTarget) -> Option<[u8; 32]> {
+        let block = self.second_digest_block(nonce);
+        let limit = u32::from_be_bytes([
+            target.target[0],
+            target.target[1],
+            target.target[2],
+            target.target[3],
+        ]);
+
+        let schedule = expand(&block);
+        let mut vars = IV;
+        for (k, word) in K.iter().zip(schedule.iter()).take(EARLY_EXIT_ROUNDS) {
+            step(&mut vars, k.wrapping_add(*word));
+        }
+        // The final `h` is this round's `e`; read as a number the hash's top
+        // word is the digest's last word with its bytes reversed.
+        if IV[7].wrapping_add(vars[4]).swap_bytes() > limit {
+            return None;
+        }
+        for round in EARLY_EXIT_ROUNDS..64 {
+            step(&mut vars, K[round].wrapping_add(schedule[round]));
+        }
+
+        let mut hash = [0u8; 32];
+        for (i, (var, iv)) in vars.iter().zip(IV).enumerate() {
+            hash[i * 4..i * 4 + 4].copy_from_slice(&var.wrapping_add(iv).to_be_bytes());
+        }
+        hash.reverse();
+        target.is_valid_hash(&hash).then_some(hash)
+    }
+
+    /// Run block 2 for `nonce` and lay out its digest as block 3.
+    fn second_digest_block(&self, nonce: u32) -> [u32; 16] {
+        let mut words = self.tail;
+        // The nonce is serialized little-endian; SHA-256 reads big-endian.
+        words[NONCE_WORD] = nonce.swap_bytes();
+        let schedule = expand(&words);
+
+        let mut vars = self.precomputed;
+        for round in NONCE_WORD..64 {
+            step(&mut vars, K[round].wrapping_add(schedule[round]));
+        }
+
+        let mut block = [0u32; 16];
+        for (word, (var, mid)) in block.iter_mut().zip(vars.iter().zip(self.midstate)) {
+            *word = var.wrapping_add(mid);
+        }
+        block[8] = 0x8000_0000;
+        block[15] = 32 * 8;
+        block
+    }
+}
+
+/// SHA-256 compression of one 16-word block into `state`.
+fn compress(state: &mut [u32; 8], block: &[u32; 16]) {
+    let schedule = expand(block);
+    let mut vars = *state;
+    for (k, word) in K.iter().zip(schedule.iter()) {
+        step(&mut vars, k.wrapping_add(*word));
+    }
+    for (value, var) in state.iter_mut().zip(vars) {
+        *value = value.wrapping_add(var);
+    }
+}
+
+/// Message schedule for one block.
+fn expand(block: &[u32; 16]) -> [u32; 64] {
+    let mut w = [0u32; 64];
+    w[..16].copy_from_slice(block);
+    for i in 16..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+    }
+    w
+}
+
+/// One SHA-256 round; `kw` is the round constant plus the message word.
+#[inline]
+#[allow(clippy::many_single_char_names)] // FIPS 180-4 names
+fn step(vars: &mut [u32; 8], kw: u32) {
+    let [a, b, c, d, e, f, g, h] = *vars;
+    let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+    let ch = (e & f) ^ (!e & g);
+    let t1 = h.wrapping_add(s1).wrapping_add(ch).wrapping_add(kw);
+    let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+    let maj = (a & b) ^ (a & c) ^ (b & c);
+    let t2 = s0.wrapping_add(maj);
+    *vars = [t1.wrapping_add(t2), a, b, c, d.wrapping_add(t1), e, f, g];
+}
+
+fn to_bytes(state: &[u32; 8]) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    for (chunk, word) in bytes.chunks_exact_mut(4).zip(state) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    bytes
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+    use crate::r#impl::{decode_hex, double_sha256, encode_hex};
+
+    fn genesis_header() -> BlockHeader {
+        let mut merkle_root =
+            decode_hex("4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b")
+                .expect("test assertion");
+        merkle_root.reverse();
+        BlockHeader {
+            version:         1,
+            prev_block_hash: [0; 32],
+            merkle_root:     merkle_root.try_into().expect("test assertion"),
+            timestamp:       1_231_006_505,
+            bits:            0x1d00_ffff,
+            nonce:           0,
+        }
+    }
+
+    /// Header with every field varied, so no byte sits at a fixed value.
+    fn scrambled_header(seed: u32) -> BlockHeader {
+        let mut prev_block_hash = [0u8; 32];
+        let mut merkle_root = [0u8; 32];
+        for i in 0..32u8 {
+            prev_block_hash[usize::from(i)] = i.wrapping_mul(37).wrapping_add(seed as u8);
+            merkle_root[usize::from(i)] = i.wrapping_mul(91) ^ (seed >> 8) as u8;
+        }
+        BlockHeader {
+            version: 0x2000_0000 | seed,
+            prev_block_hash,
+            merkle_root,
+            timestamp: 1_700_000_000 + seed,
+            bits: 0x1703_0ecd,
+            nonce: 0,
+        }
+    }
+
+    #[test]
+    fn test_hash_matches_double_sha256() {
+        for seed in [0, 1, 0xABCD, 0x1234_5678] {
+            let mut header = scrambled_header(seed);
+            let hasher = HeaderHasher::new(&header);
+            for nonce in [0, 1, 0x8000_0000, u32::MAX, seed.wrapping_mul(2_654_435_761)] {
+                header.nonce = nonce;
+                assert_eq!(hasher.hash(nonce), double_sha256(&header.serialize()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_check_finds_genesis_nonce() {
+        let header = genesis_header();
+        let hasher = HeaderHasher::new(&header);
+        let target = HashTarget::from_bits(header.bits);
+
+        let hash = hasher.check(2_083_236_893, &target).expect("test assertion");
+        assert_eq!(
+            encode_hex(&hash),
+            "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"
+        );
+        assert_eq!(hasher.check(2_083_236_892, &target), None);
+    }
+
+    #[test]
+    fn test_check_agrees_with_full_comparison() {
+        let mut header = scrambled_header(7);
+        let hasher = HeaderHasher::new(&header);
+        let mut tight = [0u8; 32];
+        tight[0] = 0x02;
+        let targets = [
+            HashTarget { target: [0xFF; 32] },
+            HashTarget { target: tight },
+            HashTarget::from_difficulty(1.0),
+        ];
+
+        let mut accepted = 0;
+        for nonce in 0..2000 {
+            header.nonce = nonce;
+            let mut expected = double_sha256(&header.serialize());
+            expected.reverse();
+            for target in &targets {
+                let result = hasher.check(nonce, target);
+                assert_eq!(result.is_some(), target.is_valid_hash(&expected), "nonce {nonce}");
+                if let Some(hash) = result {
+                    assert_eq!(hash, expected);
+                    accepted += 1;
+                }
+            }
+        }
+        // Every nonce meets the all-ones target; about 1 in 128 the tight one.
+        assert!(accepted > 2000);
+    }
+}
@@ -3,29 +3,85 @@
 //! This module contains all implementations for the Mining plugin:
 //! - `MiningConfig` - Configuration
 //! - `MiningHardwareProfile` - Hardware detection
+//! - `CpuTopology` - Logical CPUs and the cores they share
+//! - `CgroupLimits` - Container CPU quota, cpuset and memory limits
 //! - `MiningCoordinator` - Mining thread management
+//! - `WorkerPlacement` - Mining thread priority and core pinning
+//! - `LoadWatcher` - Pausing mining while the system is busy
+//! - `ThermalMonitor` - Throttling mining from CPU temperature sensors
 //! - `StratumClient` - Pool protocol client
+//! - `StratumUrl` - Pool URL parsing
+//! - `Socks5Proxy` - SOCKS5 tunnel for pool connections
+//! - `LineDecoder`/`StratumMessage` - Newline-delimited JSON-RPC codec
+//! - `JobTemplate` - Coinbase and merkle-root construction for Stratum jobs
+//! - `HeaderHasher` - Midstate SHA-256d for the hashing loop
+//! - `HashBackend` - Scalar, SSE4.1, AVX2 and SHA-NI hashing backends
+//! - `PowAlgorithm` implementations - `Sha256d` and `Scrypt` proof of work
 //! - `MiningPlugin` - Main plugin interface
 //! - `PoolManager` - Multi-pool management
 //! - `HashRateMonitor` - Hash rate tracking
 //! - `RewardDistributor` - Reward calculation and distribution
 
+mod cgroup;
 mod config;
 mod coordinator;
 mod crypto;
 mod hardware;
+mod hash_backend;
 mod hash_rate_monitor;
+mod job_builder;
+mod load_watcher;
+mod midstate;
 mod plugin;
 mod pool_management;
+mod pow;
 mod reward_distribution;
+mod scheduling;
+mod scrypt;
+mod socks5;
 mod stratum;
+mod stratum_codec;
+mod stratum_url;
+#[cfg(all(test, feature = "full-tests"))]
+mod test_tree;
+mod thermal;
+mod throttle;
+mod topology;
 
+pub use cgroup::CgroupLimits;
 pub use config::MiningConfig;
 pub use coordinator::MiningCoordinator;
 pub use crypto::{Sha256, double_sha256, sha256, sha256_hex};
 pub use hardware::MiningHardwareProfile;
+pub use hash_backend::{
+    Avx2Backend, HashBackend, MAX_HASH_LANES, ScalarBackend, Sse41Backend, ShaNiBackend,
+    select_hash_backend,
+};
 pub use hash_rate_monitor::*;
+pub use job_builder::{
+    JobTemplate, MAX_NTIME_ROLL, build_coinbase, merkle_root, prev_hash_from_stratum, roll_work,
+};
+pub use load_watcher::{LoadPolicy, LoadSample, LoadWatcher};
+pub use midstate::HeaderHasher;
 pub use plugin::MiningPlugin;
 pub use pool_management::*;
+pub use pow::Sha256d;
 pub use reward_distribution::*;
-pub use stratum::{StratumClient, parse_stratum_url};
+pub use scheduling::WorkerPlacement;
+pub use scrypt::{
+    SCRYPT_N, SCRYPT_P, SCRYPT_R, Scrypt, ScryptScratch, pbkdf2_hmac_sha256, scrypt,
+};
+pub use socks5::Socks5Proxy;
+pub use stratum::{
+    BIP320_VERSION_MASK, NegotiatedExtensions, ReconnectPolicy, StratumClient, StratumExtensions,
+    parse_stratum_url,
+};
+pub use stratum_codec::{
+    CodecError, JsonValue, LineDecoder, StratumError, StratumMessage, decode_hex, encode_hex,
+};
+pub use stratum_url::{StratumScheme, StratumUrl};
+pub use thermal::{
+    MIN_THERMAL_SCALE, RESUME_MARGIN, THROTTLE_BAND, TempSensor, ThermalMonitor, ThermalReading,
+    ThermalState,
+};
+pub use topology::{CpuTopology, LogicalCpu, MAX_CORE_CAPACITY};
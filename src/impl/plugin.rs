@@ -1,18 +1,32 @@
 //! Mining plugin implementation.
 
+use std::time::SystemTime;
+
 use crate::{
     errors::{MiningError, MiningResult},
-    r#impl::{MiningConfig, MiningCoordinator, MiningHardwareProfile, StratumClient},
+    r#impl::{
+        HashRateMonitor, HashRateMonitorConfig, LoadWatcher, MiningConfig, MiningCoordinator,
+        MiningHardwareProfile, StratumClient, StratumUrl, ThermalMonitor, ThermalState,
+    },
     traits::{MiningCoordinatorTrait, PoolClientTrait},
-    types::{MiningStats, PoolConnection},
+    types::{
+        FoundShare, MiningJob, MiningStats, PauseEvent, PauseReason, PoolConnection, ShareOutcome,
+    },
 };
 
+/// Pause events kept for [`MiningPlugin::pause_events`].
+const MAX_PAUSE_EVENTS: usize = 64;
+
 /// Main mining plugin interface.
 pub struct MiningPlugin {
-    config:           MiningConfig,
-    coordinator:      Option<MiningCoordinator>,
-    stratum_client:   Option<StratumClient>,
-    hardware_profile: MiningHardwareProfile,
+    config:            MiningConfig,
+    coordinator:       Option<MiningCoordinator>,
+    stratum_client:    Option<StratumClient>,
+    hardware_profile:  MiningHardwareProfile,
+    load_watcher:      LoadWatcher,
+    thermal_monitor:   ThermalMonitor,
+    hash_rate_monitor: HashRateMonitor,
+    pause_events:      Vec<PauseEvent>,
 }
 
 impl MiningPlugin {
@@ -32,7 +46,18 @@ impl MiningPlugin {
             ));
         }
 
-        Ok(Self { config, coordinator: None, stratum_client: None, hardware_profile })
+        let load_watcher = LoadWatcher::new(config.load_policy.clone());
+
+        Ok(Self {
+            config,
+            coordinator: None,
+            stratum_client: None,
+            hardware_profile,
+            load_watcher,
+            thermal_monitor: ThermalMonitor::new(),
+            hash_rate_monitor: HashRateMonitor::new(HashRateMonitorConfig::default()),
+            pause_events: Vec::new(),
+        })
     }
 
     /// Get hardware profile.
@@ -49,6 +74,9 @@ impl MiningPlugin {
 
     /// Update configuration.
     ///
+    /// A new CPU limit applies to running workers right away; other settings
+    /// take effect the next time mining starts.
+    ///
     /// # Errors
     ///
     /// Returns `MiningError::Configuration` if new config is invalid.
@@ -58,6 +86,9 @@ impl MiningPlugin {
                 "CPU percentage must be between 1 and 100".into(),
             ));
         }
+        if let Some(ref coordinator) = self.coordinator {
+            coordinator.set_max_cpu_percentage(config.max_cpu_percentage)?;
+        }
         self.config = config;
         Ok(())
     }
@@ -75,7 +106,8 @@ impl MiningPlugin {
             .clone()
             .ok_or_else(|| MiningError::Configuration("No pool URL configured".into()))?;
 
-        let mut client = StratumClient::new(pool_url, &self.config.worker_name);
+        let url = StratumUrl::parse(&pool_url)?;
+        let mut client = StratumClient::from_url(&url, &self.config.worker_name);
         client.connect()?;
         self.stratum_client = Some(client);
 
@@ -95,7 +127,7 @@ impl MiningPlugin {
     pub fn pool_connection_state(&self) -> PoolConnection {
         self.stratum_client
             .as_ref()
-            .map(|c| c.state().clone())
+            .map(|c| c.state())
             .unwrap_or(PoolConnection::Disconnected)
     }
 
@@ -109,7 +141,8 @@ impl MiningPlugin {
             return Err(MiningError::Coordinator("Mining already active".into()));
         }
 
-        let coordinator = MiningCoordinator::new(self.config.clone())?;
+        let coordinator =
+            MiningCoordinator::with_hardware(self.config.clone(), self.hardware_profile.clone())?;
 
         // Get job from pool or create test job
         if let Some(ref client) = self.stratum_client
@@ -122,12 +155,193 @@ impl MiningPlugin {
         Ok(())
     }
 
+    /// Poll the pool for new work and switch the workers to it.
+    ///
+    /// Starts mining if no job was available when background mining was
+    /// started. Returns whether the workers were given a new job.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MiningError::PoolConnection` if the pool cannot be reached.
+    /// Returns `MiningError::Coordinator` if the job cannot be handed over.
+    pub fn refresh_job(&self) -> MiningResult<bool> {
+        let (Some(coordinator), Some(client)) = (&self.coordinator, &self.stratum_client) else {
+            return Ok(false);
+        };
+        let Some(job) = client.get_job()? else {
+            return Ok(false);
+        };
+
+        if !coordinator.is_running() {
+            coordinator.start(job)?;
+        } else if coordinator.current_job().is_some_and(|current| is_same_work(&current, &job)) {
+            return Ok(false);
+        } else {
+            coordinator.update_job(job)?;
+        }
+        Ok(true)
+    }
+
     /// Stop background mining.
+    ///
+    /// Blocks until every mining thread has exited.
     pub fn stop_background_mining(&mut self) {
         if let Some(ref coordinator) = self.coordinator {
             coordinator.stop();
         }
         self.coordinator = None;
+        self.load_watcher.reset();
+        self.thermal_monitor.reset();
+    }
+
+    /// Sample system load and pause or resume mining for it.
+    ///
+    /// Does nothing unless `auto_pause_on_load` is set and mining is
+    /// active. Call it every few seconds; see [`LoadWatcher`] for how
+    /// pausing is decided. The returned event is also kept in
+    /// [`Self::pause_events`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `MiningError::HardwareDetection` if system load cannot be
+    /// read.
+    pub fn check_system_load(&mut self) -> MiningResult<Option<PauseEvent>> {
+        if !self.config.auto_pause_on_load {
+            return Ok(None);
+        }
+        let Some(ref coordinator) = self.coordinator else {
+            return Ok(None);
+        };
+
+        let worker_load = if coordinator.is_running() && !coordinator.is_paused() {
+            coordinator.worker_count() as f64 * coordinator.duty_cycle()
+        } else {
+            0.0
+        };
+        let Some(event) = self.load_watcher.update(worker_load)? else {
+            return Ok(None);
+        };
+        if event.paused {
+            coordinator.pause(PauseReason::SystemLoad);
+        } else {
+            coordinator.resume(PauseReason::SystemLoad);
+        }
+
+        self.record_pause_event(&event);
+        Ok(Some(event))
+    }
+
+    /// Read the CPU temperature and throttle or pause mining for it.
+    ///
+    /// Does nothing unless `thermal_throttle_temp` is set and mining is
+    /// active. Near the limit the workers' duty cycle shrinks; at the limit
+    /// mining pauses until the CPU has cooled down. Entering a hotter
+    /// regime raises `AlertType::TemperatureWarning` on
+    /// [`Self::hash_rate_monitor`]. Pausing and resuming return an event,
+    /// which is also kept in [`Self::pause_events`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `MiningError::HardwareDetection` if temperature sensors exist
+    /// but cannot be read.
+    pub fn check_temperature(&mut self) -> MiningResult<Option<PauseEvent>> {
+        let Some(limit) = self.config.thermal_throttle_temp else {
+            return Ok(None);
+        };
+        let Some(ref coordinator) = self.coordinator else {
+            return Ok(None);
+        };
+
+        let previous = self.thermal_monitor.state();
+        let Some(reading) = self.thermal_monitor.update(limit)? else {
+            return Ok(None);
+        };
+        coordinator.set_thermal_scale(reading.scale);
+        if reading.state > previous {
+            self.hash_rate_monitor.temperature_warning(reading.temperature, f64::from(limit))?;
+        }
+
+        let paused = reading.state == ThermalState::Paused;
+        if paused == (previous == ThermalState::Paused) {
+            return Ok(None);
+        }
+        if paused {
+            coordinator.pause(PauseReason::Thermal);
+        } else {
+            coordinator.resume(PauseReason::Thermal);
+        }
+
+        let event = PauseEvent {
+            paused,
+            reason:  PauseReason::Thermal,
+            at:      SystemTime::now(),
+            reading: reading.temperature,
+        };
+        self.record_pause_event(&event);
+        Ok(Some(event))
+    }
+
+    fn record_pause_event(&mut self, event: &PauseEvent) {
+        if self.pause_events.len() == MAX_PAUSE_EVENTS {
+            self.pause_events.remove(0);
+        }
+        self.pause_events.push(event.clone());
+    }
+
+    /// Hash rate statistics and alerts.
+    #[must_use]
+    pub fn hash_rate_monitor(&self) -> &HashRateMonitor {
+        &self.hash_rate_monitor
+    }
+
+    /// Recent pause and resume events, oldest first.
+    #[must_use]
+    pub fn pause_events(&self) -> &[PauseEvent] {
+        &self.pause_events
+    }
+
+    /// Check if mining is active but paused.
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.coordinator.as_ref().is_some_and(MiningCoordinator::is_paused)
+    }
+
+    /// Submit the shares found since the last call to the pool.
+    ///
+    /// Each share is submitted on its own, so a failure only costs that
+    /// share. Returns every share with the pool's verdict or the error it
+    /// failed with, in the order they were found; failed shares are counted
+    /// in `MiningStats::shares_dropped`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MiningError::PoolConnection` if no pool is connected; the
+    /// shares then stay with the coordinator.
+    pub fn submit_found_shares(
+        &self,
+    ) -> MiningResult<Vec<(FoundShare, MiningResult<ShareOutcome>)>> {
+        let Some(ref coordinator) = self.coordinator else {
+            return Ok(Vec::new());
+        };
+        let client = self
+            .stratum_client
+            .as_ref()
+            .ok_or_else(|| MiningError::PoolConnection("Not connected to pool".into()))?;
+
+        Ok(coordinator
+            .take_found_shares()
+            .into_iter()
+            .map(|share| {
+                let outcome = client.submit_share(
+                    &share.job_id,
+                    &share.extranonce2,
+                    share.ntime,
+                    share.nonce,
+                    share.version,
+                );
+                (share, outcome)
+            })
+            .collect())
     }
 
     /// Check if mining is active.
@@ -139,10 +353,25 @@ impl MiningPlugin {
     /// Get current mining statistics.
     #[must_use]
     pub fn stats(&self) -> MiningStats {
-        self.coordinator.as_ref().map(|c| c.stats()).unwrap_or_default()
+        let mut stats = self.coordinator.as_ref().map(|c| c.stats()).unwrap_or_default();
+        if let Some(ref client) = self.stratum_client {
+            stats.difficulty = client.difficulty();
+            stats.shares_accepted = client.shares_accepted();
+            stats.shares_rejected = client.shares_rejected();
+            stats.shares_dropped = client.shares_dropped();
+        }
+        stats
     }
 }
 
+/// Whether two jobs hash the same headers against the same target.
+fn is_same_work(current: &MiningJob, job: &MiningJob) -> bool {
+    current.job_id == job.job_id
+        && current.extranonce1 == job.extranonce1
+        && current.target.target == job.target.target
+        && current.version_mask == job.version_mask
+}
+
 impl Drop for MiningPlugin {
     fn drop(&mut self) {
         self.stop_background_mining();
@@ -153,6 +382,33 @@ impl Drop for MiningPlugin {
 #[cfg(all(test, feature = "full-tests"))]
 mod tests {
     use super::*;
+    use crate::{r#impl::JobTemplate, types::HashTarget};
+
+    /// Coordinator mining `job` on hardware that passes the mining
+    /// requirements on any test machine.
+    fn start_mining(config: MiningConfig, job: MiningJob) -> MiningCoordinator {
+        let coordinator =
+            MiningCoordinator::with_hardware(config, MiningHardwareProfile::test_machine())
+                .expect("test assertion");
+        coordinator.start(job).expect("test assertion");
+        coordinator
+    }
+
+    /// Job `job_id` of an empty block, mined against `target`.
+    fn job(job_id: &str, target: HashTarget) -> MiningJob {
+        let template = JobTemplate {
+            job_id:        job_id.into(),
+            prev_hash:     [0; 32],
+            coinbase1:     vec![0x01],
+            coinbase2:     vec![0x02],
+            merkle_branch: Vec::new(),
+            version:       0x2000_0000,
+            nbits:         0x1d00_ffff,
+            ntime:         1_700_000_000,
+            clean_jobs:    true,
+        };
+        template.to_job(&[0xAA], vec![0; 4], target)
+    }
 
     #[test]
     fn test_plugin_creation() {
@@ -181,6 +437,165 @@ mod tests {
         assert!(profile.logical_cores > 0);
     }
 
+    #[test]
+    fn test_no_shares_to_submit_when_idle() {
+        let plugin = MiningPlugin::new(MiningConfig::default()).expect("test assertion");
+        assert!(plugin.submit_found_shares().expect("test assertion").is_empty());
+    }
+
+    #[test]
+    fn test_refresh_job_without_pool() {
+        let plugin = MiningPlugin::new(MiningConfig::default()).expect("test assertion");
+        assert!(!plugin.refresh_job().expect("test assertion"));
+    }
+
+    #[test]
+    fn test_stop_background_mining_joins_workers() {
+        let mut plugin = MiningPlugin::new(MiningConfig::default()).expect("test assertion");
+        let job = job("stop", HashTarget::from_difficulty(1.0));
+        plugin.coordinator = Some(start_mining(plugin.config.clone(), job));
+        assert!(plugin.is_mining());
+
+        let started = std::time::Instant::now();
+        plugin.stop_background_mining();
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+        assert!(!plugin.is_mining());
+    }
+
+    #[test]
+    fn test_check_system_load_pauses_and_resumes() {
+        use crate::r#impl::{LoadPolicy, test_tree::TempTree};
+        use std::time::Duration;
+
+        let root = TempTree::new("plugin-load");
+        let write = |busy: u64, idle: u64| {
+            root.write("stat", format!("cpu  {busy} 0 0 {idle}\ncpu0 {busy} 0 0 {idle}\n"));
+            root.write("loadavg", "0.00 0.00 0.00 1/100 1\n");
+        };
+
+        let policy = LoadPolicy { resume_after: Duration::ZERO, ..LoadPolicy::default() };
+        let config = MiningConfig::default().with_load_policy(true, policy.clone());
+        let mut plugin = MiningPlugin::new(config.clone()).expect("test assertion");
+        assert_eq!(plugin.check_system_load().expect("test assertion"), None);
+
+        plugin.load_watcher = LoadWatcher::new(policy).with_proc_root(root.path());
+        let job = job("load", HashTarget::from_difficulty(1.0));
+        plugin.coordinator = Some(start_mining(config, job));
+
+        write(0, 0);
+        assert_eq!(plugin.check_system_load().expect("test assertion"), None);
+        write(90, 10);
+        let paused = plugin.check_system_load().expect("test assertion").expect("test assertion");
+        assert!(paused.paused);
+        assert!(plugin.is_paused());
+        write(100, 100);
+        let resumed = plugin.check_system_load().expect("test assertion").expect("test assertion");
+        assert!(!resumed.paused);
+        assert!(!plugin.is_paused());
+        assert_eq!(plugin.pause_events(), [paused, resumed]);
+
+        plugin.stop_background_mining();
+    }
+
+    #[test]
+    fn test_check_temperature_throttles_and_pauses() {
+        use crate::r#impl::{AlertType, test_tree::TempTree};
+
+        let root =
+            TempTree::with_files("plugin-thermal", &[("class/hwmon/hwmon0/name", "coretemp\n")]);
+        let set_temp = |celsius: u32| {
+            root.write("class/hwmon/hwmon0/temp1_input", format!("{}\n", celsius * 1000));
+        };
+
+        let config = MiningConfig::default().with_max_cpu_usage(100);
+        let mut plugin = MiningPlugin::new(config.clone()).expect("test assertion");
+        plugin.thermal_monitor = ThermalMonitor::new().with_sys_root(root.path());
+        set_temp(75);
+        assert_eq!(plugin.check_temperature().expect("test assertion"), None);
+
+        let coordinator = start_mining(config, job("thermal", HashTarget::from_difficulty(1.0)));
+        let full_duty = coordinator.duty_cycle();
+        plugin.coordinator = Some(coordinator);
+
+        // 75 °C against the default 80 °C limit: throttled, not paused.
+        assert_eq!(plugin.check_temperature().expect("test assertion"), None);
+        let duty = plugin.coordinator.as_ref().expect("test assertion").duty_cycle();
+        assert!(duty < full_duty);
+        let alerts = plugin.hash_rate_monitor().alerts().expect("test assertion");
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].alert_type, AlertType::TemperatureWarning);
+
+        set_temp(85);
+        let paused = plugin.check_temperature().expect("test assertion").expect("test assertion");
+        assert!(paused.paused);
+        assert_eq!(paused.reason, PauseReason::Thermal);
+        assert!(plugin.is_paused());
+        assert_eq!(plugin.hash_rate_monitor().alerts().expect("test assertion").len(), 2);
+
+        set_temp(70);
+        let resumed = plugin.check_temperature().expect("test assertion").expect("test assertion");
+        assert!(!resumed.paused);
+        assert!(!plugin.is_paused());
+        assert_eq!(plugin.pause_events(), [paused, resumed]);
+
+        plugin.stop_background_mining();
+    }
+
+    #[test]
+    fn test_submit_found_shares_survives_failure_midway() {
+        use std::{
+            io::{BufRead, BufReader, Write},
+            net::TcpListener,
+        };
+
+        use crate::r#impl::{JsonValue, ReconnectPolicy};
+
+        // Pool that accepts the first share and then hangs up.
+        let listener = TcpListener::bind("127.0.0.1:0").expect("test assertion");
+        let url = format!("stratum+tcp://{}", listener.local_addr().expect("test assertion"));
+        let pool = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().expect("test assertion");
+            let mut writer = stream.try_clone().expect("test assertion");
+            let mut reader = BufReader::new(stream);
+            for result in [r#"[[],"08000002",4]"#, "true", "true"] {
+                let mut line = String::new();
+                reader.read_line(&mut line).expect("test assertion");
+                let request = JsonValue::parse(line.trim()).expect("test assertion");
+                let id = request.get("id").and_then(JsonValue::as_u64).expect("test assertion");
+                writeln!(writer, r#"{{"id":{id},"result":{result},"error":null}}"#)
+                    .expect("test assertion");
+            }
+        });
+
+        let mut client = StratumClient::new(url, "worker1")
+            .with_reconnect_policy(ReconnectPolicy::disabled())
+            .with_share_queue_capacity(0);
+        client.connect().expect("test assertion");
+
+        // Every hash meets this target, so the workers find shares at once.
+        let mut plugin = MiningPlugin::new(MiningConfig::default()).expect("test assertion");
+        let job = job("easy", HashTarget { target: [0xFF; 32] });
+        let coordinator = start_mining(plugin.config.clone(), job);
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        while coordinator.stats().shares_found < 3 {
+            assert!(std::time::Instant::now() < deadline, "no shares found in time");
+            std::thread::yield_now();
+        }
+        coordinator.stop();
+        plugin.coordinator = Some(coordinator);
+        plugin.stratum_client = Some(client);
+
+        let submitted = plugin.submit_found_shares().expect("test assertion");
+        pool.join().expect("test assertion");
+        assert!(submitted.len() >= 3);
+        assert_eq!(submitted[0].1.as_ref().ok(), Some(&ShareOutcome::Accepted));
+        assert!(submitted[1..].iter().all(|(_, outcome)| outcome.is_err()));
+
+        let stats = plugin.stats();
+        assert_eq!(stats.shares_accepted, 1);
+        assert_eq!(stats.shares_dropped, submitted.len() as u64 - 1);
+    }
+
     #[test]
     fn test_pool_not_connected_initially() {
         let plugin = MiningPlugin::new(MiningConfig::default()).expect("test assertion");
@@ -9,7 +9,10 @@ use std::{
     time::{Duration, Instant},
 };
 
-use crate::errors::{MiningError, MiningResult};
+use crate::{
+    errors::{MiningError, MiningResult},
+    r#impl::{ReconnectPolicy, Socks5Proxy, StratumClient, StratumUrl},
+};
 
 /// Pool priority level.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -28,7 +31,7 @@ pub struct PoolConfig {
     /// Pool identifier.
     pub id: String,
     /// Pool URL.
-    pub url: String,
+    pub url: StratumUrl,
     /// Worker name.
     pub worker: String,
     /// Worker password (optional).
@@ -45,13 +48,15 @@ pub struct PoolConfig {
     pub retry_delay: Duration,
     /// Pool fee percentage.
     pub fee_percent: f64,
+    /// SOCKS5 proxy to reach the pool through (optional).
+    pub proxy: Option<Socks5Proxy>,
 }
 
 impl Default for PoolConfig {
     fn default() -> Self {
         Self {
             id: "default".to_string(),
-            url: "stratum+tcp://localhost:3333".to_string(),
+            url: StratumUrl::new("localhost", 3333),
             worker: "worker1".to_string(),
             password: None,
             priority: PoolPriority::Primary,
@@ -60,6 +65,46 @@ impl Default for PoolConfig {
             max_retries: 3,
             retry_delay: Duration::from_secs(5),
             fee_percent: 1.0,
+            proxy: None,
+        }
+    }
+}
+
+impl PoolConfig {
+    /// Creates a pool configuration from a URL.
+    ///
+    /// Credentials in the URL become the worker name and password.
+    pub fn from_url(id: impl Into<String>, url: &str) -> MiningResult<Self> {
+        let url = StratumUrl::parse(url)?;
+        let defaults = Self::default();
+        Ok(Self {
+            id: id.into(),
+            worker: url.username.clone().unwrap_or(defaults.worker),
+            password: url.password.clone(),
+            url,
+            ..Self::default()
+        })
+    }
+
+    /// Creates a Stratum client for this pool.
+    ///
+    /// Reconnects follow `retry_delay` and give up after `max_retries`.
+    #[must_use]
+    pub fn stratum_client(&self) -> StratumClient {
+        let client = StratumClient::new(self.url.to_string(), &self.worker)
+            .with_timeout(self.connect_timeout)
+            .with_reconnect_policy(ReconnectPolicy {
+                initial_delay: self.retry_delay,
+                max_attempts: Some(self.max_retries),
+                ..ReconnectPolicy::default()
+            });
+        let client = match self.proxy {
+            Some(ref proxy) => client.with_proxy(proxy.clone()),
+            None => client,
+        };
+        match self.password {
+            Some(ref password) => client.with_password(password.clone()),
+            None => client,
         }
     }
 }
@@ -510,6 +555,20 @@ mod tests {
         assert_eq!(state.status, PoolStatus::Disconnected);
     }
 
+    #[test]
+    fn test_pool_config_from_url() {
+        let config =
+            PoolConfig::from_url("p", "stratum+tcp://wallet.rig:pw@[2001:db8::7]:3333").unwrap();
+        assert_eq!(config.worker, "wallet.rig");
+        assert_eq!(config.password.as_deref(), Some("pw"));
+        assert_eq!(config.url.host, "2001:db8::7");
+        assert_eq!(config.url.port, 3333);
+
+        let config = PoolConfig::from_url("p", "stratum+tcp://pool.example.com:3333").unwrap();
+        assert_eq!(config.worker, PoolConfig::default().worker);
+        assert!(PoolConfig::from_url("p", "stratum+tcp://pool.example.com").is_err());
+    }
+
     #[test]
     fn test_pool_manager() {
         let manager = PoolManager::new(PoolManagerConfig::default());
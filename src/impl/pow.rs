@@ -0,0 +1,148 @@
+//! Proof-of-work algorithms.
+//!
+//! A [`PowAlgorithm`] defines how a coin's header is serialized, hashed and
+//! compared with the target; [`MiningJob::algorithm`] picks one per job, so
+//! the coordinator's hashing loop does not depend on a particular coin.
+//! [`Sha256d`] is the Bitcoin algorithm and the coordinator's default. Its
+//! search runs on a [`HashBackend`] from the [`HeaderHasher`] midstate.
+//! [`Scrypt`] serves Litecoin and Dogecoin.
+//!
+//! [`MiningJob::algorithm`]: crate::types::MiningJob::algorithm
+//! [`Scrypt`]: crate::r#impl::Scrypt
+
+use std::sync::Arc;
+
+use crate::{
+    r#impl::{HashBackend, HeaderHasher, ScalarBackend, double_sha256},
+    traits::{NonceSearch, PowAlgorithm},
+    types::{BlockHeader, HashTarget},
+};
+
+/// Double SHA-256 of the 80-byte header, as mined by Bitcoin.
+#[derive(Clone)]
+pub struct Sha256d {
+    backend: Arc<dyn HashBackend>,
+}
+
+impl Sha256d {
+    /// SHA-256d searching nonces on `backend`.
+    #[must_use]
+    pub fn new(backend: Arc<dyn HashBackend>) -> Self {
+        Self { backend }
+    }
+
+    /// Name of the hashing backend searches run on.
+    #[must_use]
+    pub fn backend(&self) -> &'static str {
+        self.backend.name()
+    }
+}
+
+impl Default for Sha256d {
+    fn default() -> Self {
+        Self::new(Arc::new(ScalarBackend))
+    }
+}
+
+impl PowAlgorithm for Sha256d {
+    fn name(&self) -> &'static str {
+        "sha256d"
+    }
+
+    fn hash(&self, data: &[u8]) -> [u8; 32] {
+        double_sha256(data)
+    }
+
+    fn search(&self, header: &BlockHeader) -> Box<dyn NonceSearch> {
+        Box::new(Sha256dSearch {
+            backend: Arc::clone(&self.backend),
+            hasher:  HeaderHasher::new(header),
+        })
+    }
+}
+
+/// SHA-256d search state: the header's midstate.
+struct Sha256dSearch {
+    backend: Arc<dyn HashBackend>,
+    hasher:  HeaderHasher,
+}
+
+impl NonceSearch for Sha256dSearch {
+    fn lanes(&self) -> usize {
+        self.backend.lanes()
+    }
+
+    fn set_header(&mut self, header: &BlockHeader) {
+        self.hasher = HeaderHasher::new(header);
+    }
+
+    fn scan(
+        &mut self, first_nonce: u32, count: usize, target: &HashTarget,
+        found: &mut dyn FnMut(u32, [u8; 32]),
+    ) {
+        self.backend.scan(&self.hasher, first_nonce, count, target, found);
+    }
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+    use crate::r#impl::{MiningHardwareProfile, decode_hex, select_hash_backend};
+
+    /// Bitcoin genesis block header.
+    fn genesis() -> BlockHeader {
+        let mut merkle_root =
+            decode_hex("4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b")
+                .expect("test assertion");
+        merkle_root.reverse();
+        BlockHeader {
+            version:         1,
+            prev_block_hash: [0; 32],
+            merkle_root:     merkle_root.try_into().expect("test assertion"),
+            timestamp:       1_231_006_505,
+            bits:            0x1d00_ffff,
+            nonce:           2_083_236_893,
+        }
+    }
+
+    #[test]
+    fn test_sha256d_hashes_genesis_block() {
+        let pow = Sha256d::default();
+        let header = genesis();
+        let digest = pow.hash(&pow.serialize_header(&header));
+        let target = HashTarget::from_bits(header.bits);
+
+        let mut expected =
+            decode_hex("000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f")
+                .expect("test assertion");
+        assert_eq!(pow.check_target(&digest, &target).map(Vec::from), Some(expected.clone()));
+        expected.reverse();
+        assert_eq!(digest.to_vec(), expected);
+        assert!(pow.check_target(&digest, &HashTarget { target: [0; 32] }).is_none());
+    }
+
+    #[test]
+    fn test_sha256d_search_agrees_with_hash() {
+        let pow = Sha256d::new(select_hash_backend(&MiningHardwareProfile::default()));
+        let mut header = genesis();
+        let target = HashTarget::from_bits(header.bits);
+        let expected = pow.check_target(&pow.hash(&pow.serialize_header(&header)), &target);
+
+        let mut search = pow.search(&header);
+        let mut found = Vec::new();
+        let mut nonce = header.nonce - 16;
+        while nonce < header.nonce + 16 {
+            search.scan(nonce, search.lanes(), &target, &mut |nonce, hash| {
+                found.push((nonce, hash));
+            });
+            nonce += search.lanes() as u32;
+        }
+        assert_eq!(found, vec![(header.nonce, expected.expect("test assertion"))]);
+
+        // After rolling ntime the genesis nonce no longer meets the target.
+        header.timestamp += 1;
+        search.set_header(&header);
+        search.scan(header.nonce, 1, &target, &mut |nonce, _| found.push((nonce, [0; 32])));
+        assert_eq!(found.len(), 1);
+    }
+}
@@ -0,0 +1,282 @@
+//! Scheduling priority and CPU affinity for mining threads.
+//!
+//! With [`MiningConfig::background_priority`] set, each worker moves itself
+//! to `SCHED_IDLE`, so it only runs when no other thread wants the CPU; if
+//! the kernel refuses, it falls back to nice 19. With
+//! [`MiningConfig::pin_workers`], each worker is pinned to one core from
+//! [`MiningHardwareProfile::mining_cores`]; otherwise, if
+//! [`MiningConfig::excluded_cores`] is not empty, workers may run on any core
+//! except the excluded ones.
+//!
+//! On a hybrid CPU, [`MiningConfig::core_preference`] confines workers to
+//! efficiency cores, keeping performance cores free for the user, or to
+//! performance cores for the highest hash rate. See
+//! [`MiningHardwareProfile::preferred_cores`].
+//!
+//! Both only take effect on Linux; elsewhere [`WorkerPlacement::apply`]
+//! does nothing.
+
+use crate::{
+    errors::{MiningError, MiningResult},
+    r#impl::{MiningConfig, MiningHardwareProfile},
+    traits::MiningHardwareTrait,
+    types::CorePreference,
+};
+
+/// Scheduling settings for one mining thread.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkerPlacement {
+    /// Run at background priority.
+    pub background: bool,
+    /// Cores the thread may run on; empty leaves the affinity unchanged.
+    pub cores:      Vec<usize>,
+}
+
+impl WorkerPlacement {
+    /// Placements for up to `threads` workers.
+    ///
+    /// Pinning, excluding cores or preferring a core class on a hybrid CPU
+    /// caps the workers at one per usable core.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MiningError::Configuration` if every core is excluded.
+    pub fn plan(
+        config: &MiningConfig, hardware: &MiningHardwareProfile, threads: usize,
+    ) -> MiningResult<Vec<Self>> {
+        let background = config.background_priority;
+        let prefer = config.core_preference != CorePreference::Any && hardware.is_hybrid();
+        if !config.pin_workers && config.excluded_cores.is_empty() && !prefer {
+            return Ok(vec![Self { background, cores: Vec::new() }; threads]);
+        }
+
+        let cores = hardware.preferred_cores(config.core_preference, &config.excluded_cores);
+        if cores.is_empty() {
+            return Err(MiningError::Configuration(
+                "All CPU cores are excluded from mining".into(),
+            ));
+        }
+
+        let threads = threads.min(cores.len());
+        Ok((0..threads)
+            .map(|worker| Self {
+                background,
+                cores: if config.pin_workers { vec![cores[worker]] } else { cores.clone() },
+            })
+            .collect())
+    }
+
+    /// Apply the placement to the calling thread.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MiningError::ResourceAllocation` if the priority or affinity
+    /// cannot be set.
+    pub fn apply(&self) -> MiningResult<()> {
+        #[cfg(target_os = "linux")]
+        {
+            if self.background {
+                linux::set_background_priority()?;
+            }
+            if !self.cores.is_empty() {
+                linux::set_affinity(&self.cores)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::{ffi::c_int, io};
+
+    use crate::errors::{MiningError, MiningResult};
+
+    pub(super) const SCHED_IDLE: c_int = 5;
+    const PRIO_PROCESS: c_int = 0;
+    const BACKGROUND_NICE: c_int = 19;
+    /// Words in a `cpu_set_t`, enough for 1024 CPUs.
+    const CPU_SET_WORDS: usize = 16;
+
+    #[repr(C)]
+    struct SchedParam {
+        sched_priority: c_int,
+    }
+
+    // A pid or tid of 0 means the calling thread for all of these.
+    unsafe extern "C" {
+        fn sched_setscheduler(pid: c_int, policy: c_int, param: *const SchedParam) -> c_int;
+        fn setpriority(which: c_int, who: u32, prio: c_int) -> c_int;
+        fn sched_setaffinity(pid: c_int, size: usize, mask: *const u64) -> c_int;
+        #[cfg(all(test, feature = "full-tests"))]
+        fn sched_getscheduler(pid: c_int) -> c_int;
+        #[cfg(all(test, feature = "full-tests"))]
+        fn sched_getaffinity(pid: c_int, size: usize, mask: *mut u64) -> c_int;
+    }
+
+    fn os_error(action: &str) -> MiningError {
+        MiningError::ResourceAllocation(format!("{action}: {}", io::Error::last_os_error()))
+    }
+
+    /// Move the calling thread to `SCHED_IDLE`, or nice 19 if not allowed.
+    pub(super) fn set_background_priority() -> MiningResult<()> {
+        let param = SchedParam { sched_priority: 0 };
+        // SAFETY: `param` is a valid `sched_param` for the duration of the call.
+        if unsafe { sched_setscheduler(0, SCHED_IDLE, &raw const param) } == 0 {
+            return Ok(());
+        }
+        // SAFETY: plain integer arguments.
+        if unsafe { setpriority(PRIO_PROCESS, 0, BACKGROUND_NICE) } == 0 {
+            return Ok(());
+        }
+        Err(os_error("Failed to lower mining thread priority"))
+    }
+
+    /// Restrict the calling thread to `cores`.
+    pub(super) fn set_affinity(cores: &[usize]) -> MiningResult<()> {
+        let mut mask = [0u64; CPU_SET_WORDS];
+        for &core in cores {
+            let word = mask.get_mut(core / 64).ok_or_else(|| {
+                MiningError::ResourceAllocation(format!("CPU core {core} is out of range"))
+            })?;
+            *word |= 1 << (core % 64);
+        }
+        // SAFETY: `mask` is a `cpu_set_t`-sized buffer that outlives the call.
+        if unsafe { sched_setaffinity(0, size_of_val(&mask), mask.as_ptr()) } == 0 {
+            Ok(())
+        } else {
+            Err(os_error("Failed to pin mining thread"))
+        }
+    }
+
+    /// Scheduling policy of the calling thread.
+    #[cfg(all(test, feature = "full-tests"))]
+    pub(super) fn current_policy() -> c_int {
+        // SAFETY: plain integer argument.
+        unsafe { sched_getscheduler(0) }
+    }
+
+    /// Cores the calling thread may run on.
+    #[cfg(all(test, feature = "full-tests"))]
+    pub(super) fn current_affinity() -> MiningResult<Vec<usize>> {
+        let mut mask = [0u64; CPU_SET_WORDS];
+        // SAFETY: `mask` is a `cpu_set_t`-sized buffer that outlives the call.
+        if unsafe { sched_getaffinity(0, size_of_val(&mask), mask.as_mut_ptr()) } != 0 {
+            return Err(os_error("Failed to read mining thread affinity"));
+        }
+        Ok((0..CPU_SET_WORDS * 64)
+            .filter(|core| mask[core / 64] & (1 << (core % 64)) != 0)
+            .collect())
+    }
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+    use crate::{
+        r#impl::{CpuTopology, LogicalCpu},
+        types::CoreClass,
+    };
+
+    fn hardware(physical_cores: usize, logical_cores: usize) -> MiningHardwareProfile {
+        MiningHardwareProfile {
+            physical_cores,
+            logical_cores,
+            topology: None,
+            cgroup: crate::r#impl::CgroupLimits::default(),
+            ..MiningHardwareProfile::default()
+        }
+    }
+
+    #[test]
+    fn test_plan_without_pinning_leaves_affinity() {
+        let config = MiningConfig::default().with_background_priority(false);
+        let plan = WorkerPlacement::plan(&config, &hardware(4, 8), 3).expect("test assertion");
+        assert_eq!(plan, vec![WorkerPlacement::default(); 3]);
+    }
+
+    #[test]
+    fn test_plan_pins_one_worker_per_core() {
+        let config = MiningConfig::default().with_core_pinning(true).with_excluded_cores([0]);
+        let plan = WorkerPlacement::plan(&config, &hardware(4, 8), 8).expect("test assertion");
+        let cores: Vec<&[usize]> =
+            plan.iter().map(|placement| placement.cores.as_slice()).collect();
+        assert_eq!(cores, [[1], [2], [3], [4], [5], [6], [7]]);
+        assert!(plan.iter().all(|placement| placement.background));
+    }
+
+    #[test]
+    fn test_plan_excluded_cores_without_pinning() {
+        let config = MiningConfig::default().with_excluded_cores([0, 4]);
+        let plan = WorkerPlacement::plan(&config, &hardware(4, 8), 2).expect("test assertion");
+        assert_eq!(plan.len(), 2);
+        assert!(plan.iter().all(|placement| placement.cores == [1, 2, 3, 5, 6, 7]));
+
+        let config = MiningConfig::default().with_excluded_cores(0..8);
+        assert!(matches!(
+            WorkerPlacement::plan(&config, &hardware(4, 8), 2),
+            Err(MiningError::Configuration(_))
+        ));
+    }
+
+    /// Two hyperthreaded P-cores (CPUs 0-3) and four E-cores (CPUs 4-7).
+    fn hybrid() -> MiningHardwareProfile {
+        let cpus = (0..8)
+            .map(|id| match id {
+                0..4 => LogicalCpu::new(id, 0, id / 2),
+                _ => LogicalCpu {
+                    class: CoreClass::Efficiency,
+                    capacity: 400,
+                    ..LogicalCpu::new(id, 0, id - 2)
+                },
+            })
+            .collect();
+        MiningHardwareProfile {
+            physical_cores: 6,
+            topology: CpuTopology::new(cpus),
+            ..hardware(6, 8)
+        }
+    }
+
+    #[test]
+    fn test_plan_prefers_core_class_on_hybrid_cpu() {
+        let config = MiningConfig::default().with_core_preference(CorePreference::Efficiency);
+        let plan = WorkerPlacement::plan(&config, &hybrid(), 6).expect("test assertion");
+        assert_eq!(plan.len(), 4);
+        assert!(plan.iter().all(|placement| placement.cores == [4, 5, 6, 7]));
+
+        let config =
+            config.with_core_preference(CorePreference::Performance).with_core_pinning(true);
+        let plan = WorkerPlacement::plan(&config, &hybrid(), 3).expect("test assertion");
+        let cores: Vec<&[usize]> =
+            plan.iter().map(|placement| placement.cores.as_slice()).collect();
+        assert_eq!(cores, [[0], [2], [1]]);
+
+        // Without E-cores left, the preference falls back to any core.
+        let config = MiningConfig::default()
+            .with_core_preference(CorePreference::Efficiency)
+            .with_excluded_cores(4..8);
+        let plan = WorkerPlacement::plan(&config, &hybrid(), 2).expect("test assertion");
+        assert!(plan.iter().all(|placement| placement.cores == [0, 2, 1, 3]));
+
+        // Non-hybrid CPUs ignore the preference.
+        let config = MiningConfig::default().with_core_preference(CorePreference::Efficiency);
+        let plan = WorkerPlacement::plan(&config, &hardware(4, 8), 2).expect("test assertion");
+        assert!(plan.iter().all(|placement| placement.cores.is_empty()));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_apply_lowers_priority_and_pins() {
+        std::thread::spawn(|| {
+            let core = linux::current_affinity().expect("test assertion")[0];
+            WorkerPlacement { background: true, cores: vec![core] }
+                .apply()
+                .expect("test assertion");
+            assert_eq!(linux::current_affinity().expect("test assertion"), [core]);
+            assert_eq!(linux::current_policy(), linux::SCHED_IDLE);
+        })
+        .join()
+        .expect("test assertion");
+    }
+}
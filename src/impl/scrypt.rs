@@ -0,0 +1,396 @@
+//! Scrypt proof of work, as mined by Litecoin and Dogecoin.
+//!
+//! The 80-byte header is both password and salt of scrypt with N = 1024,
+//! r = 1 and p = 1, and the 32-byte output is the proof-of-work hash in
+//! digest byte order. [`scrypt`] follows RFC 7914:
+//!
+//! 1. PBKDF2-HMAC-SHA256 with one iteration expands password and salt into
+//!    `p` blocks of `128 * r` bytes.
+//! 2. ROMix fills a scratchpad of `N` blocks with BlockMix, a chain of
+//!    Salsa20/8 cores, then mixes in `N` blocks read back from it in an
+//!    order that depends on the data.
+//! 3. PBKDF2-HMAC-SHA256 with the mixed blocks as salt gives the output.
+//!
+//! ROMix needs `128 * r * N` bytes, 128 KiB for the coin parameters, so each
+//! [`NonceSearch`] keeps one [`ScryptScratch`] for all its nonces.
+
+use crate::{
+    errors::{MiningError, MiningResult},
+    r#impl::Sha256,
+    traits::{NonceSearch, PowAlgorithm},
+    types::{BlockHeader, HashTarget},
+};
+
+/// CPU/memory cost parameter N of scrypt coins.
+pub const SCRYPT_N: usize = 1024;
+
+/// Block size parameter r of scrypt coins.
+pub const SCRYPT_R: usize = 1;
+
+/// Parallelization parameter p of scrypt coins.
+pub const SCRYPT_P: usize = 1;
+
+/// SHA-256 block size, the HMAC key length.
+const SHA256_BLOCK: usize = 64;
+
+/// Scrypt with N = 1024, r = 1, p = 1 over the 80-byte header.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Scrypt;
+
+impl PowAlgorithm for Scrypt {
+    fn name(&self) -> &'static str {
+        "scrypt"
+    }
+
+    fn hash(&self, data: &[u8]) -> [u8; 32] {
+        ScryptScratch::new(SCRYPT_N, SCRYPT_R).hash(data)
+    }
+
+    fn search(&self, header: &BlockHeader) -> Box<dyn NonceSearch> {
+        Box::new(ScryptSearch {
+            header:  header.serialize(),
+            scratch: ScryptScratch::new(SCRYPT_N, SCRYPT_R),
+        })
+    }
+}
+
+/// Scrypt search state: the header bytes and the ROMix scratchpad.
+struct ScryptSearch {
+    header:  [u8; 80],
+    scratch: ScryptScratch,
+}
+
+impl NonceSearch for ScryptSearch {
+    fn lanes(&self) -> usize {
+        1
+    }
+
+    fn set_header(&mut self, header: &BlockHeader) {
+        self.header = header.serialize();
+    }
+
+    fn scan(
+        &mut self, first_nonce: u32, count: usize, target: &HashTarget,
+        found: &mut dyn FnMut(u32, [u8; 32]),
+    ) {
+        if count == 0 {
+            return;
+        }
+        self.header[76..80].copy_from_slice(&first_nonce.to_le_bytes());
+        let digest = self.scratch.hash(&self.header);
+        if let Some(hash) = Scrypt.check_target(&digest, target) {
+            found(first_nonce, hash);
+        }
+    }
+}
+
+/// ROMix scratchpad and BlockMix buffers for one `N` and `r`.
+#[derive(Debug, Clone)]
+pub struct ScryptScratch {
+    n: usize,
+    r: usize,
+    v: Vec<u32>,
+    x: Vec<u32>,
+    y: Vec<u32>,
+}
+
+impl ScryptScratch {
+    /// Allocate the `128 * r * n` byte scratchpad.
+    ///
+    /// `n` must be a power of two greater than one and `r` non-zero; use
+    /// [`scrypt`] for checked parameters.
+    #[must_use]
+    pub fn new(n: usize, r: usize) -> Self {
+        let words = 32 * r;
+        Self { n, r, v: vec![0; words * n], x: vec![0; words], y: vec![0; words] }
+    }
+
+    /// Scrypt of `data` as both password and salt with p = 1, 32 bytes out.
+    #[must_use]
+    pub fn hash(&mut self, data: &[u8]) -> [u8; 32] {
+        let mut block = vec![0u8; 128 * self.r];
+        pbkdf2_hmac_sha256(data, data, 1, &mut block);
+        self.romix(&mut block);
+        let mut out = [0u8; 32];
+        pbkdf2_hmac_sha256(data, &block, 1, &mut out);
+        out
+    }
+
+    /// Replace `block` (`128 * r` bytes) with its ROMix.
+    fn romix(&mut self, block: &mut [u8]) {
+        let words = 32 * self.r;
+        for (word, bytes) in self.x.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+
+        for i in 0..self.n {
+            self.v[i * words..(i + 1) * words].copy_from_slice(&self.x);
+            block_mix(&self.x, &mut self.y, self.r);
+            std::mem::swap(&mut self.x, &mut self.y);
+        }
+        for _ in 0..self.n {
+            // Integerify: the first word of the last 64-byte block, mod N.
+            let j = self.x[words - 16] as usize & (self.n - 1);
+            for (word, stored) in self.x.iter_mut().zip(&self.v[j * words..(j + 1) * words]) {
+                *word ^= stored;
+            }
+            block_mix(&self.x, &mut self.y, self.r);
+            std::mem::swap(&mut self.x, &mut self.y);
+        }
+
+        for (bytes, word) in block.chunks_exact_mut(4).zip(&self.x) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+    }
+}
+
+/// Scrypt key derivation (RFC 7914) of `password` and `salt` into `out`.
+///
+/// # Errors
+///
+/// Returns `MiningError::HashComputation` unless `n` is a power of two
+/// greater than one and `r` and `p` are non-zero.
+pub fn scrypt(
+    password: &[u8], salt: &[u8], n: usize, r: usize, p: usize, out: &mut [u8],
+) -> MiningResult<()> {
+    if n < 2 || !n.is_power_of_two() || r == 0 || p == 0 {
+        return Err(MiningError::HashComputation(format!(
+            "invalid scrypt parameters N={n}, r={r}, p={p}"
+        )));
+    }
+    let Some(len) = p.checked_mul(r).and_then(|blocks| blocks.checked_mul(128)) else {
+        return Err(MiningError::HashComputation(format!("scrypt r={r}, p={p} is too large")));
+    };
+    if r.checked_mul(n).and_then(|blocks| blocks.checked_mul(128)).is_none() {
+        return Err(MiningError::HashComputation(format!("scrypt N={n}, r={r} is too large")));
+    }
+
+    let mut blocks = vec![0u8; len];
+    pbkdf2_hmac_sha256(password, salt, 1, &mut blocks);
+    let mut scratch = ScryptScratch::new(n, r);
+    for block in blocks.chunks_exact_mut(128 * r) {
+        scratch.romix(block);
+    }
+    pbkdf2_hmac_sha256(password, &blocks, 1, out);
+    Ok(())
+}
+
+/// PBKDF2 (RFC 8018) with HMAC-SHA256, filling `out`.
+pub fn pbkdf2_hmac_sha256(password: &[u8], salt: &[u8], iterations: u32, out: &mut [u8]) {
+    let key = hmac_key(password);
+    for (index, chunk) in (1u32..).zip(out.chunks_mut(32)) {
+        let mut u = hmac_sha256(&key, &[salt, &index.to_be_bytes()]);
+        let mut t = u;
+        for _ in 1..iterations {
+            u = hmac_sha256(&key, &[&u]);
+            for (t, u) in t.iter_mut().zip(u) {
+                *t ^= u;
+            }
+        }
+        chunk.copy_from_slice(&t[..chunk.len()]);
+    }
+}
+
+/// HMAC key padded to the block size; longer keys are hashed first.
+fn hmac_key(key: &[u8]) -> [u8; SHA256_BLOCK] {
+    let mut padded = [0u8; SHA256_BLOCK];
+    if key.len() > SHA256_BLOCK {
+        padded[..32].copy_from_slice(&crate::r#impl::sha256(key));
+    } else {
+        padded[..key.len()].copy_from_slice(key);
+    }
+    padded
+}
+
+/// HMAC-SHA256 of the concatenated `message` parts.
+fn hmac_sha256(key: &[u8; SHA256_BLOCK], message: &[&[u8]]) -> [u8; 32] {
+    let mut pad = key.map(|byte| byte ^ 0x36);
+    let mut inner = Sha256::new();
+    inner.update(&pad);
+    for part in message {
+        inner.update(part);
+    }
+    let inner = inner.finalize();
+
+    pad = key.map(|byte| byte ^ 0x5c);
+    let mut outer = Sha256::new();
+    outer.update(&pad);
+    outer.update(&inner);
+    outer.finalize()
+}
+
+/// BlockMix of `input` (`2 * r` blocks of 16 words) into `output`.
+fn block_mix(input: &[u32], output: &mut [u32], r: usize) {
+    let mut x = [0u32; 16];
+    x.copy_from_slice(&input[(2 * r - 1) * 16..]);
+    for (i, block) in input.chunks_exact(16).enumerate() {
+        for (x, word) in x.iter_mut().zip(block) {
+            *x ^= word;
+        }
+        salsa20_8(&mut x);
+        // Even blocks go to the first half, odd blocks to the second.
+        let position = (i / 2 + (i % 2) * r) * 16;
+        output[position..position + 16].copy_from_slice(&x);
+    }
+}
+
+/// The Salsa20/8 core: eight rounds, added back onto the input.
+fn salsa20_8(block: &mut [u32; 16]) {
+    const QUARTER_ROUNDS: [[usize; 4]; 8] = [
+        [0, 4, 8, 12],
+        [5, 9, 13, 1],
+        [10, 14, 2, 6],
+        [15, 3, 7, 11],
+        [0, 1, 2, 3],
+        [5, 6, 7, 4],
+        [10, 11, 8, 9],
+        [15, 12, 13, 14],
+    ];
+
+    let mut x = *block;
+    for _ in 0..4 {
+        for [a, b, c, d] in QUARTER_ROUNDS {
+            x[b] ^= x[a].wrapping_add(x[d]).rotate_left(7);
+            x[c] ^= x[b].wrapping_add(x[a]).rotate_left(9);
+            x[d] ^= x[c].wrapping_add(x[b]).rotate_left(13);
+            x[a] ^= x[d].wrapping_add(x[c]).rotate_left(18);
+        }
+    }
+    for (word, mixed) in block.iter_mut().zip(x) {
+        *word = word.wrapping_add(mixed);
+    }
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+    use crate::r#impl::{decode_hex, encode_hex};
+
+    fn words(hex: &str) -> Vec<u32> {
+        decode_hex(hex)
+            .expect("test assertion")
+            .chunks_exact(4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect()
+    }
+
+    /// Genesis header of a scrypt coin, hashes given most significant byte
+    /// first.
+    fn genesis(merkle_root: &str, timestamp: u32, nonce: u32) -> BlockHeader {
+        let mut merkle_root = decode_hex(merkle_root).expect("test assertion");
+        merkle_root.reverse();
+        BlockHeader {
+            version: 1,
+            prev_block_hash: [0; 32],
+            merkle_root: merkle_root.try_into().expect("test assertion"),
+            timestamp,
+            bits: 0x1e0f_fff0,
+            nonce,
+        }
+    }
+
+    #[test]
+    fn test_salsa20_8_rfc7914() {
+        let mut block: [u32; 16] = words(
+            "7e879a214f3ec9867ca940e641718f26baee555b8c61c1b50df846116dcd3b1d\
+             ee24f319df9b3d8514121e4b5ac5aa3276021d2909c74829edebc68db8b8c25e",
+        )
+        .try_into()
+        .expect("test assertion");
+        salsa20_8(&mut block);
+        assert_eq!(
+            block.to_vec(),
+            words(
+                "a41f859c6608cc993b81cacb020cef05044b2181a2fd337dfd7b1c6396682f29\
+                 b4393168e3c9e6bcfe6bc5b7a06d96bae424cc102c91745c24ad673dc7618f81"
+            )
+        );
+    }
+
+    #[test]
+    fn test_pbkdf2_hmac_sha256_rfc7914() {
+        let mut out = [0u8; 64];
+        pbkdf2_hmac_sha256(b"passwd", b"salt", 1, &mut out);
+        assert_eq!(
+            encode_hex(&out),
+            "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc\
+             49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783"
+        );
+    }
+
+    #[test]
+    fn test_scrypt_rfc7914() {
+        let mut out = [0u8; 64];
+        scrypt(b"", b"", 16, 1, 1, &mut out).expect("test assertion");
+        assert_eq!(
+            encode_hex(&out),
+            "77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442\
+             fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906"
+        );
+
+        scrypt(b"password", b"NaCl", 1024, 8, 16, &mut out).expect("test assertion");
+        assert_eq!(
+            encode_hex(&out),
+            "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b373162\
+             2eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640"
+        );
+
+        for (n, r, p) in [(0, 1, 1), (1000, 1, 1), (16, 0, 1), (16, 1, 0)] {
+            assert!(matches!(
+                scrypt(b"", b"", n, r, p, &mut out),
+                Err(MiningError::HashComputation(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_scrypt_hashes_genesis_blocks() {
+        let litecoin = genesis(
+            "97ddfbbae6be97fd6cdf3e7ca13232a3afff2353e29badfab7f73011edd4ced9",
+            1_317_972_665,
+            2_084_524_493,
+        );
+        let dogecoin = genesis(
+            "5b2a3f53f605d62c53e62932dac6925e3d74afa5a4b459745c36d42d0ed26a69",
+            1_386_325_540,
+            99_943,
+        );
+
+        for (header, expected) in [
+            (litecoin, "0000050c34a64b415b6b15b37f2216634b5b1669cb9a2e38d76f7213b0671e00"),
+            (dogecoin, "0000026f3f7874ca0c251314eaed2d2fcf83d7da3acfaacf59417d485310b448"),
+        ] {
+            let digest = Scrypt.hash(&Scrypt.serialize_header(&header));
+            let target = HashTarget::from_bits(header.bits);
+            let hash = Scrypt.check_target(&digest, &target).expect("test assertion");
+            assert_eq!(encode_hex(&hash), expected);
+        }
+    }
+
+    #[test]
+    fn test_scrypt_search_agrees_with_hash() {
+        let mut header = genesis(
+            "97ddfbbae6be97fd6cdf3e7ca13232a3afff2353e29badfab7f73011edd4ced9",
+            1_317_972_665,
+            2_084_524_493,
+        );
+        let target = HashTarget::from_bits(header.bits);
+        let mut search = Scrypt.search(&header);
+        let mut found = Vec::new();
+        for nonce in header.nonce - 2..=header.nonce + 2 {
+            search.scan(nonce, 1, &target, &mut |nonce, hash| found.push((nonce, hash)));
+        }
+        let expected = Scrypt.check_target(&Scrypt.hash(&header.serialize()), &target);
+        assert_eq!(found, vec![(header.nonce, expected.expect("test assertion"))]);
+
+        // An easy target shows the rolled header is hashed.
+        header.timestamp += 1;
+        search.set_header(&header);
+        let easy = HashTarget { target: [0xFF; 32] };
+        search.scan(header.nonce, 1, &easy, &mut |nonce, hash| found.push((nonce, hash)));
+        assert_eq!(
+            found[1].1,
+            Scrypt.check_target(&Scrypt.hash(&header.serialize()), &easy).expect("test assertion")
+        );
+    }
+}
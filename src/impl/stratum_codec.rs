@@ -0,0 +1,1015 @@
+//! Newline-delimited JSON-RPC codec for the Stratum protocol.
+//!
+//! Stratum v1 frames every message as one JSON object per line. This module
+//! provides a small std-only JSON value type, the three Stratum message
+//! shapes, and a streaming [`LineDecoder`] that accepts arbitrary byte chunks
+//! from a socket.
+//!
+//! ## Message shapes
+//!
+//! ```text
+//! request       {"id": 1, "method": "mining.subscribe", "params": [...]}
+//! notification  {"id": null, "method": "mining.notify", "params": [...]}
+//! response      {"id": 1, "result": ..., "error": null | [code, "msg", ...]}
+//! ```
+//!
+//! Pools differ in the details: some send string ids, JSON-RPC 2.0 error
+//! objects, `\r\n` line endings or stray blank lines. The decoder accepts
+//! all of these. A line that is not valid JSON is reported and skipped; the
+//! stream stays usable.
+
+use core::fmt;
+
+/// Upper bound for a single line; longer input is discarded.
+pub const DEFAULT_MAX_LINE_LENGTH: usize = 64 * 1024;
+
+/// Nesting limit that keeps hostile input from exhausting the stack.
+const MAX_DEPTH: usize = 64;
+
+/// Stratum codec error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodecError {
+    /// The line is not valid JSON.
+    Malformed(String),
+    /// The JSON is valid but not a Stratum message.
+    InvalidMessage(String),
+    /// The line exceeded the configured maximum length.
+    LineTooLong(usize),
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed(msg) => write!(f, "malformed JSON: {msg}"),
+            Self::InvalidMessage(msg) => write!(f, "invalid Stratum message: {msg}"),
+            Self::LineTooLong(len) => write!(f, "line of {len} bytes exceeds limit"),
+        }
+    }
+}
+
+/// JSON value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    /// `null`.
+    Null,
+    /// `true` or `false`.
+    Bool(bool),
+    /// Number without fraction or exponent that fits in `i64`.
+    Integer(i64),
+    /// Any other number.
+    Float(f64),
+    /// String.
+    String(String),
+    /// Array.
+    Array(Vec<JsonValue>),
+    /// Object with members in document order.
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    /// Parse a complete JSON document.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CodecError::Malformed` if the input is not valid JSON.
+    pub fn parse(input: &str) -> Result<Self, CodecError> {
+        let mut parser = JsonParser { bytes: input.as_bytes(), pos: 0, depth: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos != parser.bytes.len() {
+            return Err(CodecError::Malformed(format!(
+                "trailing characters at offset {}",
+                parser.pos
+            )));
+        }
+        Ok(value)
+    }
+
+    /// Serialize to compact JSON.
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(&mut out);
+        out
+    }
+
+    fn write_json(&self, out: &mut String) {
+        match self {
+            Self::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            Self::Integer(n) => out.push_str(&n.to_string()),
+            Self::Float(n) if n.is_finite() => {
+                let text = n.to_string();
+                out.push_str(&text);
+                if !text.contains(['.', 'e', 'E']) {
+                    out.push_str(".0");
+                }
+            },
+            Self::Null | Self::Float(_) => out.push_str("null"),
+            Self::String(s) => write_string(s, out),
+            Self::Array(items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    item.write_json(out);
+                }
+                out.push(']');
+            },
+            Self::Object(members) => {
+                out.push('{');
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    write_string(key, out);
+                    out.push(':');
+                    value.write_json(out);
+                }
+                out.push('}');
+            },
+        }
+    }
+
+    /// Hex-encoded string value.
+    #[must_use]
+    pub fn hex(bytes: &[u8]) -> Self {
+        Self::String(encode_hex(bytes))
+    }
+
+    /// Big-endian 8-digit hex string, as Stratum sends `ntime` and `nonce`.
+    #[must_use]
+    pub fn hex_u32(value: u32) -> Self {
+        Self::String(format!("{value:08x}"))
+    }
+
+    /// Look up an object member.
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&Self> {
+        match self {
+            Self::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// String contents.
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Boolean value.
+    #[must_use]
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Integer value.
+    #[must_use]
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Integer(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Non-negative integer value.
+    #[must_use]
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_i64().and_then(|n| u64::try_from(n).ok())
+    }
+
+    /// Numeric value.
+    #[must_use]
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Integer(n) => Some(*n as f64),
+            Self::Float(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Array elements.
+    #[must_use]
+    pub fn as_array(&self) -> Option<&[Self]> {
+        match self {
+            Self::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Bytes of a hex string value.
+    #[must_use]
+    pub fn as_hex_bytes(&self) -> Option<Vec<u8>> {
+        self.as_str().and_then(decode_hex)
+    }
+
+    /// Value of a hex-encoded 32-bit word such as `nbits` or `ntime`.
+    #[must_use]
+    pub fn as_hex_u32(&self) -> Option<u32> {
+        self.as_str()
+            .filter(|s| !s.is_empty() && s.len() <= 8 && is_hex(s.as_bytes()))
+            .and_then(|s| u32::from_str_radix(s, 16).ok())
+    }
+}
+
+impl From<&str> for JsonValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<String> for JsonValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<bool> for JsonValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<i64> for JsonValue {
+    fn from(value: i64) -> Self {
+        Self::Integer(value)
+    }
+}
+
+impl From<u32> for JsonValue {
+    fn from(value: u32) -> Self {
+        Self::Integer(value.into())
+    }
+}
+
+impl From<f64> for JsonValue {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<Vec<JsonValue>> for JsonValue {
+    fn from(value: Vec<JsonValue>) -> Self {
+        Self::Array(value)
+    }
+}
+
+/// Error member of a Stratum response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StratumError {
+    /// Error code (20 = "other/unknown" when the pool sends none).
+    pub code:    i64,
+    /// Human-readable message.
+    pub message: String,
+}
+
+impl StratumError {
+    /// Decode `[code, "message", traceback]`, `{"code", "message"}` or a bare string.
+    fn from_json(value: &JsonValue) -> Option<Self> {
+        let (code, message) = match value {
+            JsonValue::Null | JsonValue::Bool(false) => return None,
+            JsonValue::Array(items) => (items.first(), items.get(1)),
+            JsonValue::Object(_) => (value.get("code"), value.get("message")),
+            JsonValue::String(_) => (None, Some(value)),
+            other => (Some(other), None),
+        };
+        Some(Self {
+            code:    code.and_then(JsonValue::as_i64).unwrap_or(20),
+            message: match message {
+                Some(JsonValue::String(s)) => s.clone(),
+                Some(other) => other.to_json(),
+                None => value.to_json(),
+            },
+        })
+    }
+
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Array(vec![
+            JsonValue::Integer(self.code),
+            JsonValue::String(self.message.clone()),
+            JsonValue::Null,
+        ])
+    }
+}
+
+/// One Stratum message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StratumMessage {
+    /// Method call expecting a response.
+    Request {
+        /// Request id.
+        id:     u64,
+        /// Method name.
+        method: String,
+        /// Positional parameters.
+        params: Vec<JsonValue>,
+    },
+    /// Method call without an id.
+    Notification {
+        /// Method name.
+        method: String,
+        /// Positional parameters.
+        params: Vec<JsonValue>,
+    },
+    /// Answer to a request.
+    Response {
+        /// Id of the request being answered.
+        id:     u64,
+        /// Result value (`null` on error).
+        result: JsonValue,
+        /// Error, if the request failed.
+        error:  Option<StratumError>,
+    },
+}
+
+impl StratumMessage {
+    /// Build a request.
+    #[must_use]
+    pub fn request(id: u64, method: &str, params: Vec<JsonValue>) -> Self {
+        Self::Request { id, method: method.to_string(), params }
+    }
+
+    /// Parse one line of Stratum traffic.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CodecError::Malformed` for invalid JSON and
+    /// `CodecError::InvalidMessage` for JSON that is not a Stratum message.
+    pub fn parse(line: &str) -> Result<Self, CodecError> {
+        Self::from_json(JsonValue::parse(line)?)
+    }
+
+    /// Interpret a decoded JSON value.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CodecError::InvalidMessage` if the value is not a Stratum message.
+    pub fn from_json(value: JsonValue) -> Result<Self, CodecError> {
+        let JsonValue::Object(members) = value else {
+            return Err(CodecError::InvalidMessage("message is not an object".into()));
+        };
+
+        let mut id = None;
+        let mut method = None;
+        let mut params = None;
+        let mut result = None;
+        let mut error = None;
+        for (key, value) in members {
+            match key.as_str() {
+                "id" => id = Some(value),
+                "method" => method = Some(value),
+                "params" => params = Some(value),
+                "result" => result = Some(value),
+                "error" => error = Some(value),
+                _ => {},
+            }
+        }
+
+        let id = match id {
+            None | Some(JsonValue::Null) => None,
+            Some(JsonValue::String(s)) => s.trim().parse::<u64>().ok(),
+            Some(other) => other.as_u64(),
+        };
+
+        if let Some(method) = method {
+            let JsonValue::String(method) = method else {
+                return Err(CodecError::InvalidMessage("method is not a string".into()));
+            };
+            let params = match params {
+                None | Some(JsonValue::Null) => Vec::new(),
+                Some(JsonValue::Array(items)) => items,
+                Some(other) => vec![other],
+            };
+            return Ok(match id {
+                Some(id) => Self::Request { id, method, params },
+                None => Self::Notification { method, params },
+            });
+        }
+
+        let id =
+            id.ok_or_else(|| CodecError::InvalidMessage("response without a usable id".into()))?;
+        Ok(Self::Response {
+            id,
+            result: result.unwrap_or(JsonValue::Null),
+            error: error.as_ref().and_then(StratumError::from_json),
+        })
+    }
+
+    /// Convert to a JSON value.
+    #[must_use]
+    pub fn to_json(&self) -> JsonValue {
+        match self {
+            Self::Request { id, method, params } => JsonValue::Object(vec![
+                ("id".into(), JsonValue::Integer(i64::try_from(*id).unwrap_or(i64::MAX))),
+                ("method".into(), JsonValue::String(method.clone())),
+                ("params".into(), JsonValue::Array(params.clone())),
+            ]),
+            Self::Notification { method, params } => JsonValue::Object(vec![
+                ("id".into(), JsonValue::Null),
+                ("method".into(), JsonValue::String(method.clone())),
+                ("params".into(), JsonValue::Array(params.clone())),
+            ]),
+            Self::Response { id, result, error } => JsonValue::Object(vec![
+                ("id".into(), JsonValue::Integer(i64::try_from(*id).unwrap_or(i64::MAX))),
+                ("result".into(), result.clone()),
+                ("error".into(), error.as_ref().map_or(JsonValue::Null, StratumError::to_json)),
+            ]),
+        }
+    }
+
+    /// Encode as one newline-terminated line.
+    #[must_use]
+    pub fn encode_line(&self) -> String {
+        let mut line = self.to_json().to_json();
+        line.push('\n');
+        line
+    }
+}
+
+/// Streaming decoder for newline-delimited Stratum messages.
+#[derive(Debug)]
+pub struct LineDecoder {
+    buffer:          Vec<u8>,
+    /// Bytes of `buffer` already scanned for a newline.
+    scanned:         usize,
+    max_line_length: usize,
+    /// Discarding the rest of an over-long line.
+    discarding:      bool,
+}
+
+impl Default for LineDecoder {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_LINE_LENGTH)
+    }
+}
+
+impl LineDecoder {
+    /// Create a decoder that rejects lines longer than `max_line_length`.
+    #[must_use]
+    pub fn new(max_line_length: usize) -> Self {
+        Self { buffer: Vec::new(), scanned: 0, max_line_length, discarding: false }
+    }
+
+    /// Append bytes received from the stream.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Number of buffered bytes not yet decoded.
+    #[must_use]
+    pub fn pending_bytes(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Decode the next complete message.
+    ///
+    /// Returns `None` when more input is needed. Blank lines are skipped;
+    /// malformed or over-long lines are reported once and then dropped.
+    pub fn next_message(&mut self) -> Option<Result<StratumMessage, CodecError>> {
+        loop {
+            let Some(offset) = self.buffer[self.scanned..].iter().position(|&b| b == b'\n') else {
+                self.scanned = self.buffer.len();
+                if !self.discarding && self.buffer.len() > self.max_line_length {
+                    let len = self.buffer.len();
+                    self.buffer.clear();
+                    self.scanned = 0;
+                    self.discarding = true;
+                    return Some(Err(CodecError::LineTooLong(len)));
+                }
+                if self.discarding {
+                    self.buffer.clear();
+                    self.scanned = 0;
+                }
+                return None;
+            };
+
+            let end = self.scanned + offset;
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            self.scanned = 0;
+            if std::mem::take(&mut self.discarding) {
+                continue;
+            }
+            if line.len() > self.max_line_length + 1 {
+                return Some(Err(CodecError::LineTooLong(line.len())));
+            }
+
+            let Ok(text) = std::str::from_utf8(&line) else {
+                return Some(Err(CodecError::Malformed("line is not valid UTF-8".into())));
+            };
+            let text = text.trim();
+            if text.is_empty() {
+                continue;
+            }
+            return Some(StratumMessage::parse(text));
+        }
+    }
+}
+
+/// Encode bytes as lowercase hex.
+#[must_use]
+pub fn encode_hex(bytes: &[u8]) -> String {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    bytes
+        .iter()
+        .flat_map(|b| [HEX[(b >> 4) as usize] as char, HEX[(b & 0xF) as usize] as char])
+        .collect()
+}
+
+/// Decode a hex string, accepting either case.
+#[must_use]
+pub fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !is_hex(hex.as_bytes()) {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok()).collect()
+}
+
+/// Whether `digits` are all hex digits; `from_str_radix` alone would also
+/// take a leading sign such as "+f".
+fn is_hex(digits: &[u8]) -> bool {
+    digits.iter().all(u8::is_ascii_hexdigit)
+}
+
+/// Write `value` as a JSON string literal.
+fn write_string(value: &str, out: &mut String) {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                out.push_str("\\u00");
+                out.push(HEX[(c as usize) >> 4] as char);
+                out.push(HEX[(c as usize) & 0xF] as char);
+            },
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Recursive-descent JSON parser over a byte slice.
+struct JsonParser<'a> {
+    bytes: &'a [u8],
+    pos:   usize,
+    depth: usize,
+}
+
+impl JsonParser<'_> {
+    fn error(&self, what: &str) -> CodecError {
+        CodecError::Malformed(format!("{what} at offset {}", self.pos))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.bytes.len() && self.bytes[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, literal: &str) -> Result<(), CodecError> {
+        if self.bytes[self.pos..].starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{literal}`")))
+        }
+    }
+
+    fn value(&mut self) -> Result<JsonValue, CodecError> {
+        self.skip_whitespace();
+        match self.bytes.get(self.pos) {
+            Some(b'n') => self.expect("null").map(|()| JsonValue::Null),
+            Some(b't') => self.expect("true").map(|()| JsonValue::Bool(true)),
+            Some(b'f') => self.expect("false").map(|()| JsonValue::Bool(false)),
+            Some(b'"') => self.string().map(JsonValue::String),
+            Some(b'[') => self.nested(Self::array),
+            Some(b'{') => self.nested(Self::object),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn nested(
+        &mut self, parse: fn(&mut Self) -> Result<JsonValue, CodecError>,
+    ) -> Result<JsonValue, CodecError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error("nesting too deep"));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn array(&mut self) -> Result<JsonValue, CodecError> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.bytes.get(self.pos) == Some(&b']') {
+            self.pos += 1;
+            return Ok(JsonValue::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.bytes.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Array(items));
+                },
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<JsonValue, CodecError> {
+        self.pos += 1;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.bytes.get(self.pos) == Some(&b'}') {
+            self.pos += 1;
+            return Ok(JsonValue::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.bytes.get(self.pos) != Some(&b'"') {
+                return Err(self.error("expected object key"));
+            }
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(":")?;
+            members.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.bytes.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Object(members));
+                },
+                _ => return Err(self.error("expected `,` or `}`")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, CodecError> {
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let start = self.pos;
+            while self.pos < self.bytes.len() && !matches!(self.bytes[self.pos], b'"' | b'\\') {
+                if self.bytes[self.pos] < 0x20 {
+                    return Err(self.error("control character in string"));
+                }
+                self.pos += 1;
+            }
+            out.push_str(
+                std::str::from_utf8(&self.bytes[start..self.pos])
+                    .map_err(|_| self.error("invalid UTF-8 in string"))?,
+            );
+            match self.bytes.get(self.pos) {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(out);
+                },
+                Some(b'\\') => {
+                    let escape = *self
+                        .bytes
+                        .get(self.pos + 1)
+                        .ok_or_else(|| self.error("unterminated escape"))?;
+                    self.pos += 2;
+                    match escape {
+                        b'"' => out.push('"'),
+                        b'\\' => out.push('\\'),
+                        b'/' => out.push('/'),
+                        b'b' => out.push('\u{8}'),
+                        b'f' => out.push('\u{c}'),
+                        b'n' => out.push('\n'),
+                        b'r' => out.push('\r'),
+                        b't' => out.push('\t'),
+                        b'u' => out.push(self.unicode_escape()?),
+                        _ => return Err(self.error("invalid escape")),
+                    }
+                },
+                _ => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, CodecError> {
+        let code = self
+            .bytes
+            .get(self.pos..self.pos + 4)
+            .filter(|d| is_hex(d))
+            .and_then(|d| std::str::from_utf8(d).ok())
+            .and_then(|d| u32::from_str_radix(d, 16).ok())
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        self.pos += 4;
+        Ok(code)
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, CodecError> {
+        let high = self.hex4()?;
+        let code = if (0xD800..0xDC00).contains(&high) {
+            self.expect("\\u")?;
+            let low = self.hex4()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(self.error("invalid surrogate pair"));
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or_else(|| self.error("invalid code point"))
+    }
+
+    fn number(&mut self) -> Result<JsonValue, CodecError> {
+        let start = self.pos;
+        while self.pos < self.bytes.len()
+            && matches!(self.bytes[self.pos], b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+        {
+            self.pos += 1;
+        }
+        let text = std::str::from_utf8(&self.bytes[start..self.pos])
+            .map_err(|_| self.error("invalid number"))?;
+        if let Ok(n) = text.parse::<i64>() {
+            return Ok(JsonValue::Integer(n));
+        }
+        text.parse::<f64>()
+            .ok()
+            .filter(|n| n.is_finite())
+            .map(JsonValue::Float)
+            .ok_or_else(|| CodecError::Malformed(format!("invalid number at offset {start}")))
+    }
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+
+    /// Deterministic xorshift generator for property tests.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+    }
+
+    fn random_string(rng: &mut Rng) -> String {
+        const ALPHABET: &[char] =
+            &['a', 'Z', '0', ' ', '"', '\\', '\n', '\u{1}', 'é', '✓', '😀', '/'];
+        (0..rng.below(8)).map(|_| ALPHABET[rng.below(ALPHABET.len() as u64) as usize]).collect()
+    }
+
+    fn random_value(rng: &mut Rng, depth: u32) -> JsonValue {
+        match rng.below(if depth > 3 { 5 } else { 7 }) {
+            0 => JsonValue::Null,
+            1 => JsonValue::Bool(rng.below(2) == 0),
+            2 => JsonValue::Integer(rng.next().cast_signed()),
+            3 => JsonValue::Float(rng.next().cast_signed() as f64 / 1024.0 + 0.5),
+            4 => JsonValue::String(random_string(rng)),
+            5 => {
+                JsonValue::Array((0..rng.below(4)).map(|_| random_value(rng, depth + 1)).collect())
+            },
+            _ => JsonValue::Object(
+                (0..rng.below(4))
+                    .map(|_| (random_string(rng), random_value(rng, depth + 1)))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn decode_all(decoder: &mut LineDecoder) -> Vec<Result<StratumMessage, CodecError>> {
+        std::iter::from_fn(|| decoder.next_message()).collect()
+    }
+
+    #[test]
+    fn test_parse_message_shapes() {
+        let request = StratumMessage::parse(r#"{"id":1,"method":"mining.subscribe","params":[]}"#);
+        assert_eq!(
+            request.expect("test assertion"),
+            StratumMessage::request(1, "mining.subscribe", Vec::new())
+        );
+
+        let notification = StratumMessage::parse(
+            r#"{"params":[1024],"id":null,"method":"mining.set_difficulty"}"#,
+        );
+        assert!(matches!(
+            notification.expect("test assertion"),
+            StratumMessage::Notification { ref method, ref params }
+                if method == "mining.set_difficulty" && params == &[JsonValue::Integer(1024)]
+        ));
+
+        let response = StratumMessage::parse(
+            r#"{"id":"7","result":null,"error":[23,"Low difficulty share",null]}"#,
+        );
+        assert_eq!(
+            response.expect("test assertion"),
+            StratumMessage::Response {
+                id:     7,
+                result: JsonValue::Null,
+                error:  Some(StratumError { code: 23, message: "Low difficulty share".into() }),
+            }
+        );
+    }
+
+    #[test]
+    fn test_error_variants() {
+        let object = StratumMessage::parse(
+            r#"{"jsonrpc":"2.0","id":3,"error":{"code":-32601,"message":"Method not found"}}"#,
+        );
+        assert!(matches!(
+            object.expect("test assertion"),
+            StratumMessage::Response { error: Some(StratumError { code: -32601, .. }), .. }
+        ));
+
+        let string = StratumMessage::parse(r#"{"id":4,"result":false,"error":"Stale"}"#);
+        assert!(matches!(
+            string.expect("test assertion"),
+            StratumMessage::Response { error: Some(StratumError { code: 20, ref message }), .. }
+                if message == "Stale"
+        ));
+
+        let none = StratumMessage::parse(r#"{"id":5,"result":true}"#);
+        assert!(matches!(
+            none.expect("test assertion"),
+            StratumMessage::Response { error: None, result: JsonValue::Bool(true), .. }
+        ));
+    }
+
+    #[test]
+    fn test_hex_helpers() {
+        assert_eq!(JsonValue::hex(&[0x00, 0xab, 0xff]).as_str(), Some("00abff"));
+        assert_eq!(JsonValue::hex_u32(0x1d00ffff).as_str(), Some("1d00ffff"));
+        assert_eq!(JsonValue::from("1D00FFFF").as_hex_u32(), Some(0x1d00ffff));
+        assert_eq!(JsonValue::from("123456789").as_hex_u32(), None);
+        assert_eq!(JsonValue::from("0aZ0").as_hex_bytes(), None);
+        assert_eq!(JsonValue::from("abc").as_hex_bytes(), None);
+        assert_eq!(JsonValue::from("").as_hex_bytes(), Some(Vec::new()));
+    }
+
+    #[test]
+    fn test_sign_prefixed_hex_rejected() {
+        assert_eq!(JsonValue::from("+f").as_hex_bytes(), None);
+        assert_eq!(JsonValue::from("00+f").as_hex_bytes(), None);
+        assert_eq!(JsonValue::from("+1d00fff").as_hex_u32(), None);
+        assert_eq!(JsonValue::from("-1").as_hex_u32(), None);
+        assert!(JsonValue::parse(r#""\u+041""#).is_err());
+        assert!(StratumMessage::parse(r#"{"id":1,"method":"m","params":["\u+041"]}"#).is_err());
+    }
+
+    #[test]
+    fn test_decoder_odd_framing() {
+        let mut decoder = LineDecoder::default();
+        decoder.feed(b"\r\n  \n{\"id\":1,\"result\":true,\"error\":null}\r\n{\"id\":null,");
+        decoder.feed(b"\"method\":\"mining.notify\",\"params\":[]}\n{\"id\":2");
+
+        let messages = decode_all(&mut decoder);
+        assert_eq!(messages.len(), 2);
+        assert!(matches!(messages[0], Ok(StratumMessage::Response { id: 1, .. })));
+        assert!(matches!(messages[1], Ok(StratumMessage::Notification { .. })));
+        assert_eq!(decoder.pending_bytes(), 7);
+
+        decoder.feed(b",\"result\":true}\n");
+        assert!(matches!(decoder.next_message(), Some(Ok(StratumMessage::Response { id: 2, .. }))));
+        assert!(decoder.next_message().is_none());
+    }
+
+    #[test]
+    fn test_decoder_recovers_from_garbage() {
+        let mut decoder = LineDecoder::new(64);
+        decoder.feed(b"HTTP/1.1 400 Bad Request\n[1,2]\n{\"id\":1}\n\xff\xfe\n");
+        decoder.feed(&[b'x'; 100]);
+        decoder.feed(b"still too long\n{\"id\":9,\"result\":true}\n");
+
+        let messages = decode_all(&mut decoder);
+        assert!(matches!(messages[0], Err(CodecError::Malformed(_))));
+        assert!(matches!(messages[1], Err(CodecError::InvalidMessage(_))));
+        assert!(matches!(messages[2], Ok(StratumMessage::Response { id: 1, .. })));
+        assert!(matches!(messages[3], Err(CodecError::Malformed(_))));
+        assert!(matches!(messages[4], Err(CodecError::LineTooLong(_))));
+        assert!(matches!(messages[5], Ok(StratumMessage::Response { id: 9, .. })));
+        assert_eq!(messages.len(), 6);
+    }
+
+    #[test]
+    fn test_deep_nesting_rejected() {
+        let deep = format!("{}{}", "[".repeat(1000), "]".repeat(1000));
+        assert!(JsonValue::parse(&deep).is_err());
+    }
+
+    #[test]
+    fn test_property_value_roundtrip() {
+        let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+        for _ in 0..2000 {
+            let value = random_value(&mut rng, 0);
+            let encoded = value.to_json();
+            assert!(!encoded.contains('\n'), "encoded JSON must stay on one line");
+            assert_eq!(JsonValue::parse(&encoded).expect("test assertion"), value, "{encoded}");
+        }
+    }
+
+    #[test]
+    fn test_property_message_stream_roundtrip() {
+        let mut rng = Rng(0xD1B5_4A32_D192_ED03);
+        for _ in 0..200 {
+            let messages: Vec<StratumMessage> = (0..=rng.below(5))
+                .map(|i| {
+                    let params: Vec<JsonValue> =
+                        (0..rng.below(4)).map(|_| random_value(&mut rng, 1)).collect();
+                    match rng.below(3) {
+                        0 => StratumMessage::request(i, "mining.submit", params),
+                        1 => {
+                            StratumMessage::Notification { method: "mining.notify".into(), params }
+                        },
+                        _ => StratumMessage::Response {
+                            id:     i,
+                            result: random_value(&mut rng, 1),
+                            error:  None,
+                        },
+                    }
+                })
+                .collect();
+            let stream: Vec<u8> =
+                messages.iter().flat_map(|m| m.encode_line().into_bytes()).collect();
+
+            // Split the stream at random points, as a socket would.
+            let mut decoder = LineDecoder::default();
+            let mut received = Vec::new();
+            let mut rest = stream.as_slice();
+            while !rest.is_empty() {
+                let take = (1 + rng.below(16) as usize).min(rest.len());
+                decoder.feed(&rest[..take]);
+                rest = &rest[take..];
+                received.extend(
+                    decode_all(&mut decoder).into_iter().map(|m| m.expect("test assertion")),
+                );
+            }
+            assert_eq!(received, messages);
+        }
+    }
+
+    #[test]
+    fn test_fuzz_random_bytes_never_panic() {
+        const TOKENS: &[&[u8]] = &[
+            b"{",
+            b"}",
+            b"[",
+            b"]",
+            b"\"",
+            b":",
+            b",",
+            b"\n",
+            b"\\",
+            b"\\u",
+            b"d83d",
+            b"null",
+            b"\"id\"",
+            b"\"method\"",
+            b"\"result\"",
+            b"1e999",
+            b"-",
+            b"0",
+            b"\xc3",
+            b" ",
+            b"true",
+        ];
+        let mut rng = Rng(0x2545_F491_4F6C_DD1D);
+        for _ in 0..2000 {
+            let mut decoder = LineDecoder::new(256);
+            for _ in 0..rng.below(64) {
+                if rng.below(4) == 0 {
+                    decoder.feed(&[rng.next() as u8]);
+                } else {
+                    decoder.feed(TOKENS[rng.below(TOKENS.len() as u64) as usize]);
+                }
+                let _ = decode_all(&mut decoder);
+            }
+
+            // Whatever came before, a fresh valid line must still decode.
+            decoder.feed(b"\n{\"id\":42,\"result\":true}\n");
+            let tail = decode_all(&mut decoder);
+            assert!(matches!(tail.last(), Some(Ok(StratumMessage::Response { id: 42, .. }))));
+        }
+    }
+}
@@ -0,0 +1,56 @@
+//! Temporary file trees standing in for `/proc` and `/sys` in tests.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Trees created by this process so far, to keep their names apart.
+static TREES: AtomicUsize = AtomicUsize::new(0);
+
+/// Directory under the system temp directory, removed when dropped.
+pub(crate) struct TempTree(PathBuf);
+
+impl TempTree {
+    /// Empty tree whose directory name includes `name`.
+    pub(crate) fn new(name: &str) -> Self {
+        let id = TREES.fetch_add(1, Ordering::Relaxed);
+        let root = std::env::temp_dir().join(format!("mining-{name}-{}-{id}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).expect("test assertion");
+        Self(root)
+    }
+
+    /// Tree holding `files`, given as relative path and contents.
+    pub(crate) fn with_files(name: &str, files: &[(&str, &str)]) -> Self {
+        let tree = Self::new(name);
+        for (path, contents) in files {
+            tree.write(path, contents);
+        }
+        tree
+    }
+
+    /// Root of the tree.
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// `path` inside the tree.
+    pub(crate) fn join(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.0.join(path)
+    }
+
+    /// Write `contents` to `path` inside the tree, creating its parents.
+    pub(crate) fn write(&self, path: impl AsRef<Path>, contents: impl AsRef<[u8]>) {
+        let path = self.0.join(path);
+        fs::create_dir_all(path.parent().expect("test assertion")).expect("test assertion");
+        fs::write(path, contents).expect("test assertion");
+    }
+}
+
+impl Drop for TempTree {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,361 @@
+//! Thermal throttling from Linux thermal zones and hwmon sensors.
+//!
+//! [`ThermalMonitor`] discovers temperature sensors under
+//! `/sys/class/thermal/thermal_zone*/temp` and
+//! `/sys/class/hwmon/hwmon*/temp*_input`, both in millidegrees Celsius. If
+//! any of them belongs to the CPU (package zones, `coretemp`, `k10temp` and
+//! the like) only those are used, so a hot GPU or SSD does not slow down CPU
+//! mining; otherwise every sensor counts. The hottest reading decides:
+//!
+//! - More than [`THROTTLE_BAND`] below the limit: full speed.
+//! - Within the band: the duty cycle scales down linearly towards
+//!   [`MIN_THERMAL_SCALE`] as the temperature nears the limit.
+//! - At or above the limit: mining pauses until the temperature drops
+//!   [`RESUME_MARGIN`] below it.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::errors::{MiningError, MiningResult};
+
+/// Degrees Celsius below the limit where throttling starts.
+pub const THROTTLE_BAND: f64 = 10.0;
+
+/// Duty-cycle scale just below the limit.
+pub const MIN_THERMAL_SCALE: f64 = 0.25;
+
+/// Degrees Celsius below the limit needed to resume after a pause.
+pub const RESUME_MARGIN: f64 = 3.0;
+
+/// Thermal zone types and hwmon driver names that measure the CPU.
+const CPU_SENSORS: &[&str] = &[
+    "x86_pkg_temp",
+    "cpu",
+    "coretemp",
+    "k10temp",
+    "zenpower",
+    "soc_thermal",
+    "cpu_thermal",
+    "cpu-thermal",
+    "acpitz",
+];
+
+/// One temperature input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TempSensor {
+    /// Zone type or hwmon name, with the hwmon label if there is one.
+    pub label: String,
+    /// File holding the temperature in millidegrees Celsius.
+    pub path:  PathBuf,
+    /// Whether the sensor measures the CPU.
+    pub cpu:   bool,
+}
+
+/// Thermal regime mining is in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ThermalState {
+    /// Well below the limit.
+    Normal,
+    /// Close to the limit; the duty cycle is scaled down.
+    Throttled,
+    /// At or above the limit; mining is paused.
+    Paused,
+}
+
+/// Result of one temperature check.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThermalReading {
+    /// Hottest temperature, in degrees Celsius.
+    pub temperature: f64,
+    /// Sensor that reported it.
+    pub sensor:      String,
+    /// Duty-cycle scale to apply.
+    pub scale:       f64,
+    /// Regime after this reading.
+    pub state:       ThermalState,
+}
+
+/// Reads CPU temperatures and maps them to throttling decisions.
+#[derive(Debug, Clone)]
+pub struct ThermalMonitor {
+    sys_root: PathBuf,
+    sensors:  Vec<TempSensor>,
+    state:    ThermalState,
+}
+
+impl ThermalMonitor {
+    /// Create a monitor reading `/sys`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            sys_root: PathBuf::from("/sys"),
+            sensors:  Vec::new(),
+            state:    ThermalState::Normal,
+        }
+    }
+
+    /// Read `class/thermal` and `class/hwmon` under `root` instead of
+    /// `/sys`.
+    #[must_use]
+    pub fn with_sys_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.sys_root = root.into();
+        self.sensors.clear();
+        self
+    }
+
+    /// Sensors in use, discovering them if none are known yet.
+    pub fn sensors(&mut self) -> &[TempSensor] {
+        if self.sensors.is_empty() {
+            self.sensors = discover(&self.sys_root);
+        }
+        &self.sensors
+    }
+
+    /// Current regime.
+    #[must_use]
+    pub fn state(&self) -> ThermalState {
+        self.state
+    }
+
+    /// Return to [`ThermalState::Normal`], e.g. after mining stopped.
+    pub fn reset(&mut self) {
+        self.state = ThermalState::Normal;
+    }
+
+    /// Hottest sensor reading, in degrees Celsius.
+    ///
+    /// Returns `None` if the machine exposes no usable sensor.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MiningError::HardwareDetection` if sensors exist but none
+    /// can be read.
+    pub fn hottest(&mut self) -> MiningResult<Option<(f64, String)>> {
+        let sensors = self.sensors();
+        if sensors.is_empty() {
+            return Ok(None);
+        }
+
+        let hottest = sensors
+            .iter()
+            .filter_map(|sensor| Some((read_millidegrees(&sensor.path)?, sensor)))
+            .max_by(|(a, _), (b, _)| a.total_cmp(b));
+        if let Some((temperature, sensor)) = hottest {
+            return Ok(Some((temperature, sensor.label.clone())));
+        }
+        // Sensors can disappear (hot-unplug, driver reload); look again next
+        // time.
+        self.sensors.clear();
+        Err(MiningError::HardwareDetection("No temperature sensor readable".into()))
+    }
+
+    /// Read the temperature and decide how hard to throttle against
+    /// `limit` degrees Celsius.
+    ///
+    /// # Errors
+    ///
+    /// Returns `MiningError::HardwareDetection` if sensors exist but none
+    /// can be read.
+    pub fn update(&mut self, limit: u8) -> MiningResult<Option<ThermalReading>> {
+        let Some((temperature, sensor)) = self.hottest()? else {
+            return Ok(None);
+        };
+        let limit = f64::from(limit);
+
+        let scale = thermal_scale(temperature, limit);
+        self.state = if temperature >= limit
+            || (self.state == ThermalState::Paused && temperature > limit - RESUME_MARGIN)
+        {
+            ThermalState::Paused
+        } else if scale < 1.0 {
+            ThermalState::Throttled
+        } else {
+            ThermalState::Normal
+        };
+
+        Ok(Some(ThermalReading { temperature, sensor, scale, state: self.state }))
+    }
+}
+
+impl Default for ThermalMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Duty-cycle scale for `temperature` against `limit`.
+fn thermal_scale(temperature: f64, limit: f64) -> f64 {
+    let into_band = (temperature - (limit - THROTTLE_BAND)) / THROTTLE_BAND;
+    1.0 - into_band.clamp(0.0, 1.0) * (1.0 - MIN_THERMAL_SCALE)
+}
+
+/// All sensors under `sys_root`, CPU sensors only if there are any.
+fn discover(sys_root: &Path) -> Vec<TempSensor> {
+    let mut sensors = Vec::new();
+
+    for zone in entries(&sys_root.join("class/thermal"), "thermal_zone") {
+        let label = read_trimmed(&zone.join("type")).unwrap_or_else(|| file_name(&zone));
+        let cpu = is_cpu_sensor(&label);
+        sensors.push(TempSensor { label, path: zone.join("temp"), cpu });
+    }
+
+    for hwmon in entries(&sys_root.join("class/hwmon"), "hwmon") {
+        let name = read_trimmed(&hwmon.join("name")).unwrap_or_else(|| file_name(&hwmon));
+        let cpu = is_cpu_sensor(&name);
+        for input in entries(&hwmon, "temp") {
+            let Some(index) = file_name(&input).strip_suffix("_input").map(str::to_owned) else {
+                continue;
+            };
+            let label = match read_trimmed(&hwmon.join(format!("{index}_label"))) {
+                Some(label) => format!("{name} {label}"),
+                None => name.clone(),
+            };
+            sensors.push(TempSensor { label, path: input, cpu });
+        }
+    }
+
+    if sensors.iter().any(|sensor| sensor.cpu) {
+        sensors.retain(|sensor| sensor.cpu);
+    }
+    sensors
+}
+
+fn is_cpu_sensor(name: &str) -> bool {
+    CPU_SENSORS.iter().any(|cpu| name.eq_ignore_ascii_case(cpu))
+}
+
+/// Paths in `dir` whose names start with `prefix`, sorted.
+fn entries(dir: &Path, prefix: &str) -> Vec<PathBuf> {
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = read_dir
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| file_name(path).starts_with(prefix))
+        .collect();
+    paths.sort();
+    paths
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    let text = fs::read_to_string(path).ok()?;
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_owned())
+}
+
+fn read_millidegrees(path: &Path) -> Option<f64> {
+    let millidegrees: i64 = read_trimmed(path)?.parse().ok()?;
+    Some(millidegrees as f64 / 1000.0)
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+    use crate::r#impl::test_tree::TempTree;
+
+    /// Fake `/sys` with a CPU package zone, a Wi-Fi zone, `coretemp` and an
+    /// NVMe drive.
+    struct FakeSys(TempTree);
+
+    impl FakeSys {
+        fn new() -> Self {
+            let files = [
+                ("class/thermal/thermal_zone0/type", "x86_pkg_temp\n"),
+                ("class/thermal/thermal_zone1/type", "iwlwifi_1\n"),
+                ("class/thermal/thermal_zone1/temp", "90000\n"),
+                ("class/thermal/cooling_device0/type", "Processor\n"),
+                ("class/hwmon/hwmon0/name", "coretemp\n"),
+                ("class/hwmon/hwmon0/temp1_label", "Package id 0\n"),
+                ("class/hwmon/hwmon0/temp2_label", "Core 0\n"),
+                ("class/hwmon/hwmon1/name", "nvme\n"),
+                ("class/hwmon/hwmon1/temp1_input", "95000\n"),
+            ];
+            let fake = Self(TempTree::with_files("thermal", &files));
+            fake.set_cpu(40.0);
+            fake
+        }
+
+        /// Set the package temperature; cores run two degrees cooler.
+        fn set_cpu(&self, celsius: f64) {
+            let millidegrees = |celsius: f64| format!("{}\n", (celsius * 1000.0) as i64);
+            for (path, celsius) in [
+                ("class/thermal/thermal_zone0/temp", celsius - 1.0),
+                ("class/hwmon/hwmon0/temp1_input", celsius),
+                ("class/hwmon/hwmon0/temp2_input", celsius - 2.0),
+            ] {
+                self.0.write(path, millidegrees(celsius));
+            }
+        }
+    }
+
+    #[test]
+    fn test_discovers_cpu_sensors() {
+        let sys = FakeSys::new();
+        let mut monitor = ThermalMonitor::new().with_sys_root(sys.0.path());
+        let labels: Vec<&str> =
+            monitor.sensors().iter().map(|sensor| sensor.label.as_str()).collect();
+        assert_eq!(labels, ["x86_pkg_temp", "coretemp Package id 0", "coretemp Core 0"]);
+
+        let (temperature, sensor) =
+            monitor.hottest().expect("test assertion").expect("test assertion");
+        assert!((temperature - 40.0).abs() < 1e-9);
+        assert_eq!(sensor, "coretemp Package id 0");
+    }
+
+    #[test]
+    fn test_falls_back_to_all_sensors() {
+        let sys = FakeSys::new();
+        fs::remove_dir_all(sys.0.join("class/hwmon/hwmon0")).expect("test assertion");
+        sys.0.write("class/thermal/thermal_zone0/type", "pch\n");
+        let mut monitor = ThermalMonitor::new().with_sys_root(sys.0.path());
+        assert_eq!(monitor.sensors().len(), 3);
+        let (temperature, sensor) =
+            monitor.hottest().expect("test assertion").expect("test assertion");
+        assert!((temperature - 95.0).abs() < 1e-9);
+        assert_eq!(sensor, "nvme");
+    }
+
+    #[test]
+    fn test_throttles_near_limit_and_pauses_above() {
+        let sys = FakeSys::new();
+        let mut monitor = ThermalMonitor::new().with_sys_root(sys.0.path());
+
+        let reading = monitor.update(80).expect("test assertion").expect("test assertion");
+        assert_eq!(reading.state, ThermalState::Normal);
+        assert!((reading.scale - 1.0).abs() < 1e-9);
+
+        sys.set_cpu(75.0);
+        let reading = monitor.update(80).expect("test assertion").expect("test assertion");
+        assert_eq!(reading.state, ThermalState::Throttled);
+        assert!((reading.scale - 0.625).abs() < 1e-9);
+
+        sys.set_cpu(80.0);
+        assert_eq!(
+            monitor.update(80).expect("test assertion").map(|r| r.state),
+            Some(ThermalState::Paused)
+        );
+        // Stays paused until RESUME_MARGIN below the limit.
+        sys.set_cpu(78.0);
+        assert_eq!(
+            monitor.update(80).expect("test assertion").map(|r| r.state),
+            Some(ThermalState::Paused)
+        );
+        sys.set_cpu(76.5);
+        assert_eq!(
+            monitor.update(80).expect("test assertion").map(|r| r.state),
+            Some(ThermalState::Throttled)
+        );
+    }
+
+    #[test]
+    fn test_no_sensors() {
+        let mut monitor = ThermalMonitor::new().with_sys_root("/nonexistent");
+        assert_eq!(monitor.update(80).expect("test assertion"), None);
+    }
+}
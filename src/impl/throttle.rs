@@ -0,0 +1,323 @@
+//! Duty-cycle throttling for mining threads.
+//!
+//! Thread count alone can only give whole cores to mining. To hit
+//! `max_cpu_percentage` exactly, each worker also runs on a duty cycle: between
+//! batches it calls [`Throttle::pace`], which compares the CPU time the thread
+//! has used since its window opened with the wall time that has passed, and
+//! sleeps off any excess. Counting CPU time rather than wall time keeps the
+//! ratio right when the thread is preempted. Windows restart every
+//! [`WINDOW`]; a new duty applies from the next `pace` call.
+//!
+//! A thermal scale in `0.0..=1.0` multiplies the duty cycle, so a hot CPU
+//! slows every worker down without changing the CPU limit.
+//!
+//! The throttle also pauses workers outright. Each [`PauseReason`] holds
+//! its own pause, and workers only resume once every reason is cleared.
+
+use std::{
+    sync::{
+        Condvar, Mutex,
+        atomic::{AtomicBool, AtomicU8, AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use crate::types::PauseReason;
+
+/// Accounting window of the duty cycle.
+pub(crate) const WINDOW: Duration = Duration::from_millis(100);
+
+/// Fraction of each thread's time to spend hashing, so that `threads`
+/// workers together use `percentage` of `cores`.
+pub(crate) fn duty_cycle(percentage: u8, cores: f64, threads: usize) -> f64 {
+    let budget = cores * f64::from(percentage) / 100.0;
+    (budget / threads.max(1) as f64).clamp(0.0, 1.0)
+}
+
+/// Duty cycle shared by all workers of a coordinator.
+#[derive(Debug)]
+pub(crate) struct Throttle {
+    duty:       AtomicU64,
+    /// Thermal factor applied to `duty`.
+    scale:      AtomicU64,
+    /// One bit per active `PauseReason`.
+    paused:     AtomicU8,
+    /// Bumped to cut idle periods short.
+    generation: Mutex<u64>,
+    changed:    Condvar,
+}
+
+/// One worker's accounting window.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DutyWindow {
+    wall:  Instant,
+    cpu:   Option<Duration>,
+    slept: Duration,
+}
+
+impl DutyWindow {
+    pub(crate) fn start() -> Self {
+        Self { wall: Instant::now(), cpu: thread_cpu_time(), slept: Duration::ZERO }
+    }
+
+    /// Time the thread has been busy since the window opened.
+    fn busy(&self) -> Duration {
+        match (self.cpu, thread_cpu_time()) {
+            (Some(start), Some(now)) => now.saturating_sub(start),
+            _ => self.wall.elapsed().saturating_sub(self.slept),
+        }
+    }
+}
+
+impl Throttle {
+    pub(crate) fn new(duty: f64) -> Self {
+        Self {
+            duty:       AtomicU64::new(duty.clamp(0.0, 1.0).to_bits()),
+            scale:      AtomicU64::new(1.0f64.to_bits()),
+            paused:     AtomicU8::new(0),
+            generation: Mutex::new(0),
+            changed:    Condvar::new(),
+        }
+    }
+
+    /// Duty cycle the workers run at, after thermal scaling.
+    pub(crate) fn duty(&self) -> f64 {
+        f64::from_bits(self.duty.load(Ordering::Relaxed))
+            * f64::from_bits(self.scale.load(Ordering::Relaxed))
+    }
+
+    /// Change the duty cycle of running workers.
+    pub(crate) fn set_duty(&self, duty: f64) {
+        self.duty.store(duty.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+        self.wake();
+    }
+
+    /// Scale the duty cycle by `scale`, clamped to `0.0..=1.0`.
+    pub(crate) fn set_scale(&self, scale: f64) {
+        self.scale.store(scale.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+        self.wake();
+    }
+
+    /// Hold workers at their next `pace` call until `resume(reason)`.
+    pub(crate) fn pause(&self, reason: PauseReason) {
+        self.paused.fetch_or(reason_bit(reason), Ordering::SeqCst);
+    }
+
+    /// Clear `reason`; workers continue once no reason is left.
+    pub(crate) fn resume(&self, reason: PauseReason) {
+        self.paused.fetch_and(!reason_bit(reason), Ordering::SeqCst);
+        self.wake();
+    }
+
+    pub(crate) fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst) != 0
+    }
+
+    /// Whether `reason` is holding the workers.
+    pub(crate) fn is_paused_for(&self, reason: PauseReason) -> bool {
+        self.paused.load(Ordering::SeqCst) & reason_bit(reason) != 0
+    }
+
+    /// End the current idle period of every worker early.
+    pub(crate) fn wake(&self) {
+        if let Ok(mut generation) = self.generation.lock() {
+            *generation += 1;
+        }
+        self.changed.notify_all();
+    }
+
+    /// Sleep as long as needed to bring `window` back to the duty cycle, or
+    /// while paused.
+    pub(crate) fn pace(&self, window: &mut DutyWindow, running: &AtomicBool) {
+        if self.is_paused() {
+            while self.is_paused() && running.load(Ordering::Relaxed) {
+                self.idle(WINDOW, running);
+            }
+            *window = DutyWindow::start();
+            return;
+        }
+        let duty = self.duty();
+        if duty < 1.0 {
+            let owed = if duty > 0.0 { window.busy().div_f64(duty) } else { WINDOW };
+            let idle = owed.saturating_sub(window.wall.elapsed());
+            if !idle.is_zero() {
+                let started = Instant::now();
+                self.idle(idle, running);
+                window.slept += started.elapsed();
+            }
+        }
+        if window.wall.elapsed() >= WINDOW {
+            *window = DutyWindow::start();
+        }
+    }
+
+    /// Sleep for `duration`, until `wake` or until `running` is cleared.
+    fn idle(&self, duration: Duration, running: &AtomicBool) {
+        let deadline = Instant::now() + duration;
+        let Ok(mut generation) = self.generation.lock() else {
+            return;
+        };
+        let woken_at = *generation;
+        while running.load(Ordering::Relaxed) && *generation == woken_at {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            generation = match self.changed.wait_timeout(generation, remaining) {
+                Ok((generation, _)) => generation,
+                Err(_) => return,
+            };
+        }
+    }
+}
+
+fn reason_bit(reason: PauseReason) -> u8 {
+    match reason {
+        PauseReason::SystemLoad => 1,
+        PauseReason::Thermal => 2,
+    }
+}
+
+/// CPU time used by the calling thread, where the platform reports it.
+fn thread_cpu_time() -> Option<Duration> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::ffi::{c_int, c_long};
+
+        const CLOCK_THREAD_CPUTIME_ID: c_int = 3;
+
+        #[repr(C)]
+        struct Timespec {
+            tv_sec:  c_long,
+            tv_nsec: c_long,
+        }
+
+        unsafe extern "C" {
+            fn clock_gettime(clock: c_int, time: *mut Timespec) -> c_int;
+        }
+
+        let mut time = Timespec { tv_sec: 0, tv_nsec: 0 };
+        // SAFETY: `time` is a valid `timespec` for the duration of the call.
+        if unsafe { clock_gettime(CLOCK_THREAD_CPUTIME_ID, &raw mut time) } == 0 {
+            return Some(Duration::new(time.tv_sec as u64, time.tv_nsec as u32));
+        }
+    }
+    None
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use std::{hint::black_box, sync::Arc};
+
+    use super::*;
+    use crate::r#impl::sha256;
+
+    #[test]
+    fn test_duty_cycle_splits_budget() {
+        assert!((duty_cycle(30, 4.0, 2) - 0.6).abs() < 1e-9);
+        assert!((duty_cycle(25, 8.0, 2) - 1.0).abs() < 1e-9);
+        assert!((duty_cycle(30, 4.0, 1) - 1.0).abs() < 1e-9);
+        assert!((duty_cycle(100, 2.0, 2) - 1.0).abs() < 1e-9);
+        assert!((duty_cycle(100, 1.5, 2) - 0.75).abs() < 1e-9);
+    }
+
+    /// CPU time over wall time of a worker running at `throttle`'s duty for
+    /// `duration`.
+    fn measure(throttle: &Throttle, duration: Duration) -> f64 {
+        let running = AtomicBool::new(true);
+        let started = Instant::now();
+        let cpu = thread_cpu_time().expect("test assertion");
+        let mut window = DutyWindow::start();
+        let mut data = [0u8; 64];
+        while started.elapsed() < duration {
+            for _ in 0..200 {
+                let digest = sha256(black_box(&data));
+                data[..32].copy_from_slice(&digest);
+            }
+            throttle.pace(&mut window, &running);
+        }
+        let cpu = thread_cpu_time().expect("test assertion").saturating_sub(cpu);
+        cpu.as_secs_f64() / started.elapsed().as_secs_f64()
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_pace_tracks_measured_cpu_time() {
+        let throttle = Arc::new(Throttle::new(0.3));
+        let worker = Arc::clone(&throttle);
+        let (low, high) = std::thread::spawn(move || {
+            let low = measure(&worker, Duration::from_millis(1500));
+            worker.set_duty(0.6);
+            let high = measure(&worker, Duration::from_millis(1500));
+            (low, high)
+        })
+        .join()
+        .expect("test assertion");
+
+        // Other tests share the CPU, so a worker may get less than its duty
+        // but never much more.
+        assert!((0.15..0.36).contains(&low), "30% duty used {low:.3} of a core");
+        assert!((0.3..0.68).contains(&high), "60% duty used {high:.3} of a core");
+    }
+
+    #[test]
+    fn test_scale_multiplies_duty() {
+        let throttle = Throttle::new(0.8);
+        throttle.set_scale(0.5);
+        assert!((throttle.duty() - 0.4).abs() < 1e-9);
+        throttle.set_duty(0.6);
+        assert!((throttle.duty() - 0.3).abs() < 1e-9);
+        throttle.set_scale(2.0);
+        assert!((throttle.duty() - 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_pause_reasons_are_independent() {
+        let throttle = Throttle::new(1.0);
+        throttle.pause(PauseReason::SystemLoad);
+        throttle.pause(PauseReason::Thermal);
+        throttle.resume(PauseReason::SystemLoad);
+        assert!(throttle.is_paused());
+        assert!(throttle.is_paused_for(PauseReason::Thermal));
+        throttle.resume(PauseReason::Thermal);
+        assert!(!throttle.is_paused());
+    }
+
+    #[test]
+    fn test_pause_holds_workers_until_resumed() {
+        let throttle = Arc::new(Throttle::new(1.0));
+        throttle.pause(PauseReason::SystemLoad);
+        assert!(throttle.is_paused_for(PauseReason::SystemLoad));
+
+        let worker = Arc::clone(&throttle);
+        let paced = std::thread::spawn(move || {
+            worker.pace(&mut DutyWindow::start(), &AtomicBool::new(true));
+        });
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!paced.is_finished());
+
+        throttle.resume(PauseReason::SystemLoad);
+        assert!(!throttle.is_paused());
+        paced.join().expect("test assertion");
+    }
+
+    #[test]
+    fn test_wake_ends_idle_early() {
+        let throttle = Throttle::new(0.5);
+        let running = AtomicBool::new(false);
+        let started = Instant::now();
+        throttle.idle(Duration::from_secs(10), &running);
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        let throttle = Arc::new(throttle);
+        let sleeper = Arc::clone(&throttle);
+        let idle = std::thread::spawn(move || {
+            sleeper.idle(Duration::from_secs(10), &AtomicBool::new(true));
+        });
+        while !idle.is_finished() {
+            assert!(started.elapsed() < Duration::from_secs(5), "idle worker not woken");
+            throttle.set_duty(1.0);
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+}
@@ -0,0 +1,388 @@
+//! CPU topology from Linux sysfs and `/proc/cpuinfo`.
+//!
+//! [`CpuTopology::from_sysfs`] reads the online CPUs from
+//! `/sys/devices/system/cpu/online` and each one's package and core from
+//! `cpuN/topology`. Where sysfs is unavailable,
+//! [`CpuTopology::from_cpuinfo`] uses the `physical id` and `core id` fields
+//! of `/proc/cpuinfo`; CPUs without them (as on most ARM kernels) count as
+//! one core each. Hyperthread siblings share a package and core id.
+//!
+//! On hybrid CPUs, sysfs also tells the kinds of core apart:
+//!
+//! - Intel lists its P-cores in `/sys/devices/cpu_core/cpus` and its E-cores
+//!   in `/sys/devices/cpu_atom/cpus`.
+//! - ARM big.LITTLE kernels give each CPU a `cpu_capacity` relative to the
+//!   fastest core, which has 1024; the cluster with the lowest capacity
+//!   holds the efficiency cores. On tri-cluster SoCs the middle cluster
+//!   counts as performance cores along with the prime core.
+//!
+//! Capacity defaults to 1024 where the kernel does not report it.
+
+use std::{fs, path::Path};
+
+use crate::types::CoreClass;
+
+/// Capacity of the fastest core on the kernel's scale.
+pub const MAX_CORE_CAPACITY: u32 = 1024;
+
+/// One logical CPU and the physical core it belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogicalCpu {
+    /// CPU number as the kernel and `sched_setaffinity` use it.
+    pub id:       usize,
+    /// Physical package (socket).
+    pub package:  usize,
+    /// Core within the package.
+    pub core:     usize,
+    /// Kind of core.
+    pub class:    CoreClass,
+    /// Compute capacity, [`MAX_CORE_CAPACITY`] for the fastest core.
+    pub capacity: u32,
+}
+
+impl LogicalCpu {
+    /// A CPU of a non-hybrid processor.
+    #[must_use]
+    pub fn new(id: usize, package: usize, core: usize) -> Self {
+        Self { id, package, core, class: CoreClass::Performance, capacity: MAX_CORE_CAPACITY }
+    }
+}
+
+/// Online logical CPUs of the machine, ordered by id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuTopology {
+    cpus: Vec<LogicalCpu>,
+}
+
+impl CpuTopology {
+    /// Build a topology from `cpus`.
+    ///
+    /// Returns `None` if `cpus` is empty.
+    #[must_use]
+    pub fn new(mut cpus: Vec<LogicalCpu>) -> Option<Self> {
+        if cpus.is_empty() {
+            return None;
+        }
+        cpus.sort_by_key(|cpu| cpu.id);
+        cpus.dedup_by_key(|cpu| cpu.id);
+        Some(Self { cpus })
+    }
+
+    /// Read the topology from the sysfs tree under `sys_root`, such as
+    /// `/sys`.
+    ///
+    /// Returns `None` if the online CPU list is missing or empty.
+    #[must_use]
+    pub fn from_sysfs(sys_root: &Path) -> Option<Self> {
+        let cpu_dir = sys_root.join("devices/system/cpu");
+        let read = |path: &Path| fs::read_to_string(path).ok()?.trim().parse::<usize>().ok();
+        let online = parse_cpu_list(&fs::read_to_string(cpu_dir.join("online")).ok()?)?;
+        let mut cpus: Vec<LogicalCpu> = online
+            .into_iter()
+            .map(|id| {
+                let cpu = cpu_dir.join(format!("cpu{id}"));
+                let topology = cpu.join("topology");
+                LogicalCpu {
+                    package: read(&topology.join("physical_package_id")).unwrap_or(0),
+                    // Without a core id the CPU is its own core.
+                    core: read(&topology.join("core_id")).unwrap_or(id),
+                    capacity: read(&cpu.join("cpu_capacity"))
+                        .and_then(|capacity| u32::try_from(capacity).ok())
+                        .unwrap_or(MAX_CORE_CAPACITY),
+                    ..LogicalCpu::new(id, 0, id)
+                }
+            })
+            .collect();
+
+        let atom = fs::read_to_string(sys_root.join("devices/cpu_atom/cpus"))
+            .ok()
+            .and_then(|list| parse_cpu_list(&list));
+        let capacities = cpus.iter().map(|cpu| cpu.capacity);
+        let top = capacities.clone().max().unwrap_or(MAX_CORE_CAPACITY);
+        let lowest = capacities.min().unwrap_or(MAX_CORE_CAPACITY);
+        for cpu in &mut cpus {
+            let efficiency = match atom {
+                Some(ref atom) => atom.contains(&cpu.id),
+                None => cpu.capacity == lowest && lowest < top,
+            };
+            if efficiency {
+                cpu.class = CoreClass::Efficiency;
+            }
+        }
+        Self::new(cpus)
+    }
+
+    /// Parse the contents of `/proc/cpuinfo`.
+    ///
+    /// Returns `None` if it lists no processors.
+    #[must_use]
+    pub fn from_cpuinfo(cpuinfo: &str) -> Option<Self> {
+        let mut cpus = Vec::new();
+        for block in cpuinfo.split("\n\n") {
+            let field = |key: &str| {
+                block.lines().find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    (name.trim() == key).then(|| value.trim().parse::<usize>().ok())?
+                })
+            };
+            let Some(id) = field("processor") else {
+                continue;
+            };
+            cpus.push(LogicalCpu::new(
+                id,
+                field("physical id").unwrap_or(0),
+                field("core id").unwrap_or(id),
+            ));
+        }
+        Self::new(cpus)
+    }
+
+    /// All online CPUs, ordered by id.
+    #[must_use]
+    pub fn cpus(&self) -> &[LogicalCpu] {
+        &self.cpus
+    }
+
+    /// The CPU numbered `id`, if it is online.
+    #[must_use]
+    pub fn cpu(&self, id: usize) -> Option<&LogicalCpu> {
+        self.cpus.binary_search_by_key(&id, |cpu| cpu.id).ok().map(|index| &self.cpus[index])
+    }
+
+    /// Whether both performance and efficiency cores are online.
+    #[must_use]
+    pub fn is_hybrid(&self) -> bool {
+        let has = |class| self.cpus.iter().any(|cpu| cpu.class == class);
+        has(CoreClass::Performance) && has(CoreClass::Efficiency)
+    }
+
+    /// Number of online logical CPUs.
+    #[must_use]
+    pub fn logical_cores(&self) -> usize {
+        self.cpus.len()
+    }
+
+    /// Number of distinct physical cores.
+    #[must_use]
+    pub fn physical_cores(&self) -> usize {
+        self.count_cores(|_| true)
+    }
+
+    /// Number of distinct physical cores that have at least one of `cpus`.
+    #[must_use]
+    pub fn physical_cores_among(&self, cpus: &[usize]) -> usize {
+        self.count_cores(|cpu| cpus.contains(&cpu.id))
+    }
+
+    fn count_cores(&self, include: impl Fn(&LogicalCpu) -> bool) -> usize {
+        let mut cores: Vec<(usize, usize)> = self
+            .cpus
+            .iter()
+            .filter(|cpu| include(cpu))
+            .map(|cpu| (cpu.package, cpu.core))
+            .collect();
+        cores.sort_unstable();
+        cores.dedup();
+        cores.len()
+    }
+
+    /// CPU ids with the first thread of every physical core ahead of any
+    /// hyperthread sibling, so taking CPUs from the front spreads work
+    /// over cores.
+    #[must_use]
+    pub fn spread_order(&self) -> Vec<usize> {
+        let mut ranked: Vec<(usize, usize)> = self
+            .cpus
+            .iter()
+            .map(|cpu| {
+                // `cpus` is sorted by id, so this is the CPU's position
+                // among its siblings.
+                let sibling = self
+                    .cpus
+                    .iter()
+                    .take_while(|other| other.id < cpu.id)
+                    .filter(|other| (other.package, other.core) == (cpu.package, cpu.core))
+                    .count();
+                (sibling, cpu.id)
+            })
+            .collect();
+        ranked.sort_unstable();
+        ranked.into_iter().map(|(_, id)| id).collect()
+    }
+}
+
+/// Parse a kernel CPU list such as `0-3,8,10-11`.
+///
+/// Returns `None` if the list is empty or malformed.
+pub(crate) fn parse_cpu_list(list: &str) -> Option<Vec<usize>> {
+    let list = list.trim();
+    if list.is_empty() {
+        return None;
+    }
+    let mut cpus = Vec::new();
+    for range in list.split(',') {
+        match range.split_once('-') {
+            Some((start, end)) => {
+                let start: usize = start.trim().parse().ok()?;
+                let end: usize = end.trim().parse().ok()?;
+                if start > end {
+                    return None;
+                }
+                cpus.extend(start..=end);
+            },
+            None => cpus.push(range.trim().parse().ok()?),
+        }
+    }
+    Some(cpus)
+}
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+    use crate::r#impl::test_tree::TempTree;
+
+    /// `/proc/cpuinfo` excerpt of a single-socket, two-core CPU with
+    /// hyperthreading.
+    const CPUINFO_2C4T: &str = "\
+processor\t: 0
+vendor_id\t: GenuineIntel
+model name\t: Intel(R) Core(TM) i3-7100U CPU @ 2.40GHz
+physical id\t: 0
+siblings\t: 4
+core id\t\t: 0
+cpu cores\t: 2
+
+processor\t: 1
+physical id\t: 0
+core id\t\t: 1
+
+processor\t: 2
+physical id\t: 0
+core id\t\t: 0
+
+processor\t: 3
+physical id\t: 0
+core id\t\t: 1
+";
+
+    /// `/proc/cpuinfo` excerpt of an ARM board, which has no topology
+    /// fields.
+    const CPUINFO_ARM: &str = "\
+processor\t: 0
+BogoMIPS\t: 108.00
+Features\t: fp asimd evtstrm crc32 cpuid
+
+processor\t: 1
+BogoMIPS\t: 108.00
+
+Hardware\t: BCM2835
+";
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11\n"), Some(vec![0, 1, 2, 3, 8, 10, 11]));
+        assert_eq!(parse_cpu_list("5"), Some(vec![5]));
+        assert_eq!(parse_cpu_list(""), None);
+        assert_eq!(parse_cpu_list("3-1"), None);
+        assert_eq!(parse_cpu_list("0-x"), None);
+    }
+
+    #[test]
+    fn test_from_cpuinfo() {
+        let topology = CpuTopology::from_cpuinfo(CPUINFO_2C4T).expect("test assertion");
+        assert_eq!(topology.logical_cores(), 4);
+        assert_eq!(topology.physical_cores(), 2);
+        assert_eq!(topology.spread_order(), [0, 1, 2, 3]);
+
+        let topology = CpuTopology::from_cpuinfo(CPUINFO_ARM).expect("test assertion");
+        assert_eq!(topology.logical_cores(), 2);
+        assert_eq!(topology.physical_cores(), 2);
+
+        assert_eq!(CpuTopology::from_cpuinfo("Hardware\t: BCM2835\n"), None);
+    }
+
+    #[test]
+    fn test_from_sysfs_with_adjacent_siblings() {
+        let root = TempTree::new("topology");
+        // Two sockets with two cores each; siblings are numbered next to
+        // each other, and CPU 7 is offline.
+        root.write("devices/system/cpu/online", "0-6\n");
+        for id in 0..8 {
+            let topology = format!("devices/system/cpu/cpu{id}/topology");
+            root.write(format!("{topology}/physical_package_id"), format!("{}\n", id / 4));
+            root.write(format!("{topology}/core_id"), format!("{}\n", (id % 4) / 2));
+        }
+
+        let topology = CpuTopology::from_sysfs(root.path()).expect("test assertion");
+        assert_eq!(topology.logical_cores(), 7);
+        assert_eq!(topology.physical_cores(), 4);
+        assert_eq!(topology.cpus()[5], LogicalCpu::new(5, 1, 0));
+        assert_eq!(topology.spread_order(), [0, 2, 4, 6, 1, 3, 5]);
+        assert_eq!(topology.physical_cores_among(&[0, 1, 5]), 2);
+        assert!(!topology.is_hybrid());
+
+        assert_eq!(CpuTopology::from_sysfs(&root.join("missing")), None);
+    }
+
+    #[test]
+    fn test_intel_hybrid_core_types() {
+        let root = TempTree::new("topology");
+        // Two hyperthreaded P-cores (CPUs 0-3) and four E-cores (CPUs 4-7).
+        root.write("devices/system/cpu/online", "0-7\n");
+        for id in 0..8 {
+            let core = if id < 4 { id / 2 } else { id - 2 };
+            root.write(format!("devices/system/cpu/cpu{id}/topology/core_id"), format!("{core}\n"));
+        }
+        root.write("devices/cpu_core/cpus", "0-3\n");
+        root.write("devices/cpu_atom/cpus", "4-7\n");
+
+        let topology = CpuTopology::from_sysfs(root.path()).expect("test assertion");
+        assert!(topology.is_hybrid());
+        assert_eq!(topology.physical_cores(), 6);
+        let class = |id| topology.cpu(id).expect("test assertion").class;
+        assert_eq!(class(1), CoreClass::Performance);
+        assert_eq!(class(4), CoreClass::Efficiency);
+        assert_eq!(topology.cpu(9), None);
+    }
+
+    #[test]
+    fn test_arm_big_little_capacity() {
+        let root = TempTree::new("topology");
+        // Four LITTLE cores at 446 and four big cores at 1024.
+        root.write("devices/system/cpu/online", "0-7\n");
+        for id in 0..8 {
+            let capacity = if id < 4 { 446 } else { 1024 };
+            root.write(format!("devices/system/cpu/cpu{id}/cpu_capacity"), format!("{capacity}\n"));
+        }
+
+        let topology = CpuTopology::from_sysfs(root.path()).expect("test assertion");
+        assert!(topology.is_hybrid());
+        let little = topology.cpu(2).expect("test assertion");
+        assert_eq!((little.class, little.capacity), (CoreClass::Efficiency, 446));
+        let big = topology.cpu(6).expect("test assertion");
+        assert_eq!((big.class, big.capacity), (CoreClass::Performance, 1024));
+    }
+
+    #[test]
+    fn test_arm_tri_cluster_capacity() {
+        let root = TempTree::new("topology");
+        // Four LITTLE cores at 325, three big cores at 870 and one prime
+        // core at 1024.
+        root.write("devices/system/cpu/online", "0-7\n");
+        for id in 0..8 {
+            let capacity = match id {
+                0..4 => 325,
+                4..7 => 870,
+                _ => 1024,
+            };
+            root.write(format!("devices/system/cpu/cpu{id}/cpu_capacity"), format!("{capacity}\n"));
+        }
+
+        let topology = CpuTopology::from_sysfs(root.path()).expect("test assertion");
+        assert!(topology.is_hybrid());
+        let class = |id| topology.cpu(id).expect("test assertion").class;
+        assert_eq!(class(3), CoreClass::Efficiency);
+        assert_eq!(class(4), CoreClass::Performance);
+        assert_eq!(class(7), CoreClass::Performance);
+    }
+}
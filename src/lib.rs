@@ -29,15 +29,17 @@
 //!
 //! ## Features
 //!
-//! - **Hardware Detection**: Leverages `essentia_hwdetect` for CPU/GPU
-//!   capability detection
-//! - **Background Processing**: Uses `essentia_async_runtime` for non-blocking
-//!   mining
+//! - **Hardware Detection**: Reads CPU topology and memory from `/proc` and
+//!   sysfs, and instruction set extensions from CPUID
+//! - **Background Processing**: Mines on dedicated, named OS threads that are
+//!   joined on stop
 //! - **Resource Management**: Integrates with `essentia_resource_management`
 //!   for CPU throttling
 //! - **Pool Support**: Stratum protocol implementation for mining pool
 //!   integration
 //! - **SHA-256 Implementation**: Pure Rust SHA-256 for Proof-of-Work validation
+//! - **Proof-of-Work Algorithms**: `PowAlgorithm` chosen per job: SHA-256d by
+//!   default, or Scrypt for Litecoin-family coins
 //!
 //! ## Usage
 //!
@@ -85,11 +87,14 @@ pub mod flexforge;
 pub use errors::{MiningError, MiningResult};
 pub use flexforge::{MiningDisplayStats, MiningPluginFlexForge, MiningUiConfig};
 pub use r#impl::{
-    MiningConfig, MiningCoordinator, MiningHardwareProfile, MiningPlugin, Sha256, StratumClient,
-    double_sha256, parse_stratum_url, sha256, sha256_hex,
+    MiningConfig, MiningCoordinator, MiningHardwareProfile, MiningPlugin, Scrypt, Sha256,
+    Sha256d, StratumClient, double_sha256, parse_stratum_url, sha256, sha256_hex,
+};
+pub use traits::{MiningCoordinatorTrait, MiningHardwareTrait, PoolClientTrait, PowAlgorithm};
+pub use types::{
+    BlockHeader, HashTarget, MiningJob, MiningStats, Nonce, PoolConnection, ShareOutcome,
+    ShareRejectReason,
 };
-pub use traits::{MiningCoordinatorTrait, MiningHardwareTrait, PoolClientTrait};
-pub use types::{BlockHeader, HashTarget, MiningJob, MiningStats, Nonce, PoolConnection};
 
 #[cfg(all(test, feature = "full-tests"))]
 mod tests {
@@ -1,8 +1,12 @@
 //! Core mining traits.
 
+use std::fmt;
+
 use crate::{
     errors::MiningResult,
-    types::{MiningJob, MiningStats, PoolConnection},
+    types::{
+        BlockHeader, CoreClass, HashTarget, MiningJob, MiningStats, PoolConnection, ShareOutcome,
+    },
 };
 
 /// Trait for hardware detection and profiling.
@@ -18,6 +22,16 @@ pub trait MiningHardwareTrait: Send + Sync {
 
     /// Get recommended thread count based on max CPU percentage.
     fn recommended_threads(&self, max_percentage: u8) -> usize;
+
+    /// Whether the CPU mixes performance and efficiency cores.
+    fn is_hybrid(&self) -> bool;
+
+    /// Class of logical CPU `cpu`, or `None` if it is unknown or offline.
+    fn core_class(&self, cpu: usize) -> Option<CoreClass>;
+
+    /// Compute capacity of logical CPU `cpu` on the kernel's scale, where
+    /// the fastest core is 1024; `None` if it is unknown or offline.
+    fn core_capacity(&self, cpu: usize) -> Option<u32>;
 }
 
 /// Trait for mining coordination.
@@ -25,7 +39,14 @@ pub trait MiningCoordinatorTrait: Send + Sync {
     /// Start mining with the given job.
     fn start(&self, job: MiningJob) -> MiningResult<()>;
 
+    /// Switch running workers to a new job without stopping them.
+    ///
+    /// A job with `clean_jobs` set interrupts the current work immediately.
+    fn update_job(&self, job: MiningJob) -> MiningResult<()>;
+
     /// Stop all mining threads.
+    ///
+    /// Returns once every mining thread has exited.
     fn stop(&self);
 
     /// Check if mining is currently running.
@@ -35,6 +56,61 @@ pub trait MiningCoordinatorTrait: Send + Sync {
     fn stats(&self) -> MiningStats;
 }
 
+/// Trait for proof-of-work algorithms a job can be mined with.
+///
+/// The one-shot methods define the algorithm; [`Self::search`] is the fast
+/// path the mining threads use and must agree with them.
+pub trait PowAlgorithm: Send + Sync {
+    /// Short algorithm name, e.g. `"sha256d"`.
+    fn name(&self) -> &'static str;
+
+    /// Bytes of `header` that are hashed.
+    fn serialize_header(&self, header: &BlockHeader) -> Vec<u8> {
+        header.serialize().to_vec()
+    }
+
+    /// Proof-of-work hash of serialized header bytes, in digest byte order.
+    fn hash(&self, data: &[u8]) -> [u8; 32];
+
+    /// The hash of `digest`, most significant byte first, if it meets
+    /// `target`.
+    ///
+    /// By default the digest is read as a little-endian number.
+    fn check_target(&self, digest: &[u8; 32], target: &HashTarget) -> Option<[u8; 32]> {
+        let mut hash = *digest;
+        hash.reverse();
+        target.is_valid_hash(&hash).then_some(hash)
+    }
+
+    /// Start a nonce search over `header` for one mining thread.
+    fn search(&self, header: &BlockHeader) -> Box<dyn NonceSearch>;
+}
+
+impl fmt::Debug for dyn PowAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// One mining thread's search state for a [`PowAlgorithm`].
+pub trait NonceSearch {
+    /// Number of nonces hashed per batch.
+    fn lanes(&self) -> usize;
+
+    /// Continue with `header`, e.g. after extranonce 2 or ntime was rolled;
+    /// its nonce is ignored.
+    fn set_header(&mut self, header: &BlockHeader);
+
+    /// Hash `count` nonces from `first_nonce` and report those that meet
+    /// `target`, with the hash most significant byte first.
+    ///
+    /// `count` is capped at `lanes()`.
+    fn scan(
+        &mut self, first_nonce: u32, count: usize, target: &HashTarget,
+        found: &mut dyn FnMut(u32, [u8; 32]),
+    );
+}
+
 /// Trait for pool client implementations.
 pub trait PoolClientTrait: Send + Sync {
     /// Connect to the mining pool.
@@ -44,7 +120,7 @@ pub trait PoolClientTrait: Send + Sync {
     fn disconnect(&mut self);
 
     /// Get current connection state.
-    fn state(&self) -> &PoolConnection;
+    fn state(&self) -> PoolConnection;
 
     /// Check if connected.
     fn is_connected(&self) -> bool;
@@ -52,8 +128,11 @@ pub trait PoolClientTrait: Send + Sync {
     /// Get current mining job from pool.
     fn get_job(&self) -> MiningResult<Option<MiningJob>>;
 
-    /// Submit a share to the pool.
+    /// Submit a share to the pool and wait for its verdict.
+    ///
+    /// `version` is the header version the share was hashed with; its rolled
+    /// bits are sent when the pool agreed to version rolling.
     fn submit_share(
-        &self, job_id: &str, extranonce2: &[u8], ntime: u32, nonce: u32,
-    ) -> MiningResult<bool>;
+        &self, job_id: &str, extranonce2: &[u8], ntime: u32, nonce: u32, version: u32,
+    ) -> MiningResult<ShareOutcome>;
 }
@@ -4,4 +4,6 @@
 
 mod core;
 
-pub use core::{MiningCoordinatorTrait, MiningHardwareTrait, PoolClientTrait};
+pub use core::{
+    MiningCoordinatorTrait, MiningHardwareTrait, NonceSearch, PoolClientTrait, PowAlgorithm,
+};
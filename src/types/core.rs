@@ -1,5 +1,12 @@
 //! Core mining types.
 
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use crate::traits::PowAlgorithm;
+
 /// Statistics for mining operations.
 #[derive(Debug, Clone, Default)]
 pub struct MiningStats {
@@ -13,14 +20,18 @@ pub struct MiningStats {
     pub shares_accepted:         u64,
     /// Number of shares rejected by pool.
     pub shares_rejected:         u64,
+    /// Number of shares dropped before the pool gave a verdict.
+    pub shares_dropped:          u64,
     /// Current difficulty.
     pub difficulty:              f64,
     /// Estimated time to find block (seconds).
     pub estimated_time_to_block: Option<f64>,
+    /// Time the workers took to switch to the latest updated job.
+    pub restart_latency:         Option<Duration>,
 }
 
 /// Bitcoin block header for mining.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BlockHeader {
     /// Block version.
     pub version:         u32,
@@ -80,7 +91,36 @@ impl HashTarget {
         Self { target }
     }
 
+    /// Create a share target from pool difficulty.
+    ///
+    /// Difficulty 1 is the target `0x00000000FFFF0000...` (`0xFFFF * 2^208`);
+    /// higher difficulties divide it down proportionally.
+    #[must_use]
+    pub fn from_difficulty(difficulty: f64) -> Self {
+        if difficulty.is_nan() || difficulty <= 0.0 {
+            return Self { target: [0xFF; 32] };
+        }
+
+        let mut value = 65535.0 / difficulty * 2f64.powi(208);
+        if value >= 2f64.powi(256) {
+            return Self { target: [0xFF; 32] };
+        }
+
+        let mut target = [0u8; 32];
+        for (byte, position) in target.iter_mut().zip((0..32).rev()) {
+            let weight = 2f64.powi(8 * position);
+            let digit = (value / weight).floor().min(255.0);
+            *byte = digit as u8;
+            value -= digit * weight;
+        }
+
+        Self { target }
+    }
+
     /// Check if hash meets target (hash <= target).
+    ///
+    /// `hash` is compared most significant byte first, so a raw
+    /// `double_sha256` digest must be reversed before the comparison.
     #[must_use]
     pub fn is_valid_hash(&self, hash: &[u8; 32]) -> bool {
         for (i, hash_byte) in hash.iter().enumerate() {
@@ -101,12 +141,27 @@ pub struct Nonce(pub u32);
 
 impl Nonce {
     /// Increment nonce, returning None on overflow.
+    ///
+    /// On overflow the nonce space of the header is exhausted;
+    /// [`Nonce::increment_or_roll`] rolls extranonce 2 or ntime and carries
+    /// on.
     pub fn increment(&mut self) -> Option<u32> {
         self.0 = self.0.checked_add(1)?;
         Some(self.0)
     }
 }
 
+/// Coinbase halves and merkle branch for rebuilding a job's merkle root.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CoinbaseParts {
+    /// Coinbase transaction bytes before the extranonces.
+    pub coinbase1:     Vec<u8>,
+    /// Coinbase transaction bytes after the extranonces.
+    pub coinbase2:     Vec<u8>,
+    /// Merkle branch hashes in internal byte order.
+    pub merkle_branch: Vec<[u8; 32]>,
+}
+
 /// Mining job from pool.
 #[derive(Debug, Clone)]
 pub struct MiningJob {
@@ -120,6 +175,176 @@ pub struct MiningJob {
     pub extranonce1:      Vec<u8>,
     /// Extra nonce 2 size.
     pub extranonce2_size: usize,
+    /// Extra nonce 2 used to build the header's merkle root.
+    pub extranonce2:      Vec<u8>,
+    /// Whether previous jobs must be abandoned.
+    pub clean_jobs:       bool,
+    /// Header version bits the miner may roll (BIP320); zero disables rolling.
+    pub version_mask:     u32,
+    /// Coinbase parts for rolling extranonce 2; `None` keeps it fixed.
+    pub coinbase:         Option<CoinbaseParts>,
+    /// Latest header timestamp the miner may roll to.
+    pub max_ntime:        u32,
+    /// Proof-of-work algorithm; `None` uses the coordinator's default.
+    pub algorithm:        Option<Arc<dyn PowAlgorithm>>,
+}
+
+impl MiningJob {
+    /// Header version with `bits` substituted inside the version mask.
+    #[must_use]
+    pub fn rolled_version(&self, bits: u32) -> u32 {
+        (self.header.version & !self.version_mask) | (bits & self.version_mask)
+    }
+}
+
+/// Share found by a mining thread, ready for `mining.submit`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoundShare {
+    /// Job the share was found for.
+    pub job_id:      String,
+    /// Extra nonce 2 the header's merkle root was built with.
+    pub extranonce2: Vec<u8>,
+    /// Header timestamp.
+    pub ntime:       u32,
+    /// Winning nonce.
+    pub nonce:       u32,
+    /// Header version, including any rolled bits.
+    pub version:     u32,
+    /// Header hash, most significant byte first.
+    pub hash:        [u8; 32],
+}
+
+/// Kind of core on a hybrid CPU.
+///
+/// CPUs with only one kind of core report every core as `Performance`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CoreClass {
+    /// Big core: Intel P-core, ARM big cluster.
+    Performance,
+    /// Small core: Intel E-core, ARM LITTLE cluster.
+    Efficiency,
+}
+
+/// Which cores mining threads should run on when the CPU is hybrid.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CorePreference {
+    /// Any core.
+    #[default]
+    Any,
+    /// Efficiency cores, leaving performance cores to interactive work.
+    Efficiency,
+    /// Performance cores, for the highest hash rate.
+    Performance,
+}
+
+/// Why mining was paused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PauseReason {
+    /// Other work is keeping the system busy.
+    SystemLoad,
+    /// The CPU reached its temperature limit.
+    Thermal,
+}
+
+/// Mining was paused or resumed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PauseEvent {
+    /// `true` when mining was paused, `false` when it resumed.
+    pub paused:  bool,
+    /// What triggered the change.
+    pub reason:  PauseReason,
+    /// When it happened.
+    pub at:      SystemTime,
+    /// Reading that triggered it, e.g. the share of CPU used by other work.
+    pub reading: f64,
+}
+
+/// Pool verdict on a submitted share.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShareOutcome {
+    /// Share accepted.
+    Accepted,
+    /// Pool unreachable; the share is held and resubmitted after reconnect.
+    Queued,
+    /// Share rejected.
+    Rejected(ShareRejectReason),
+}
+
+impl ShareOutcome {
+    /// Check if the share was accepted.
+    #[must_use]
+    pub fn is_accepted(&self) -> bool {
+        matches!(self, Self::Accepted)
+    }
+}
+
+/// Reason a pool rejected a share.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShareRejectReason {
+    /// Pool no longer knows the job (Stratum error 21).
+    JobNotFound,
+    /// Share was already submitted (Stratum error 22).
+    Duplicate,
+    /// Hash does not meet the share target (Stratum error 23).
+    LowDifficulty,
+    /// Share was for an outdated block.
+    Stale,
+    /// Worker is not authorized (Stratum error 24).
+    Unauthorized,
+    /// Client is not subscribed (Stratum error 25).
+    NotSubscribed,
+    /// Any other pool error.
+    Other {
+        /// Pool error code.
+        code:    i64,
+        /// Pool error message.
+        message: String,
+    },
+}
+
+impl ShareRejectReason {
+    /// Classify a Stratum error code and message.
+    ///
+    /// Standard codes take precedence; pools that only send the generic
+    /// code 20 are classified by message text.
+    #[must_use]
+    pub fn from_stratum(code: i64, message: &str) -> Self {
+        match code {
+            21 => return Self::JobNotFound,
+            22 => return Self::Duplicate,
+            23 => return Self::LowDifficulty,
+            24 => return Self::Unauthorized,
+            25 => return Self::NotSubscribed,
+            _ => {},
+        }
+
+        let lower = message.to_ascii_lowercase();
+        if lower.contains("stale") {
+            Self::Stale
+        } else if lower.contains("job not found") {
+            Self::JobNotFound
+        } else if lower.contains("duplicate") {
+            Self::Duplicate
+        } else if lower.contains("low difficulty") || lower.contains("above target") {
+            Self::LowDifficulty
+        } else {
+            Self::Other { code, message: message.to_string() }
+        }
+    }
+}
+
+impl core::fmt::Display for ShareRejectReason {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::JobNotFound => write!(f, "job not found"),
+            Self::Duplicate => write!(f, "duplicate share"),
+            Self::LowDifficulty => write!(f, "low difficulty share"),
+            Self::Stale => write!(f, "stale share"),
+            Self::Unauthorized => write!(f, "unauthorized worker"),
+            Self::NotSubscribed => write!(f, "not subscribed"),
+            Self::Other { code, message } => write!(f, "error {code}: {message}"),
+        }
+    }
 }
 
 /// Pool connection state.
@@ -148,3 +373,84 @@ pub enum PoolConnection {
         reason: String,
     },
 }
+
+#[cfg(all(test, feature = "full-tests"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_from_difficulty_one() {
+        let target = HashTarget::from_difficulty(1.0);
+        let mut expected = [0u8; 32];
+        expected[4] = 0xFF;
+        expected[5] = 0xFF;
+        assert_eq!(target.target, expected);
+    }
+
+    #[test]
+    fn test_target_from_difficulty_scales() {
+        let target = HashTarget::from_difficulty(2.0);
+        let mut expected = [0u8; 32];
+        expected[4] = 0x7F;
+        expected[5] = 0xFF;
+        expected[6] = 0x80;
+        assert_eq!(target.target, expected);
+
+        assert_eq!(HashTarget::from_difficulty(0.0).target, [0xFF; 32]);
+    }
+
+    #[test]
+    fn test_rolled_version_stays_inside_mask() {
+        let mut job = MiningJob {
+            job_id:           "1".into(),
+            header:           BlockHeader {
+                version:         0x2000_0004,
+                prev_block_hash: [0; 32],
+                merkle_root:     [0; 32],
+                timestamp:       0,
+                bits:            0x1d00_ffff,
+                nonce:           0,
+            },
+            target:           HashTarget::from_difficulty(1.0),
+            extranonce1:      Vec::new(),
+            extranonce2_size: 4,
+            extranonce2:      vec![0; 4],
+            clean_jobs:       false,
+            version_mask:     0,
+            coinbase:         None,
+            max_ntime:        0,
+            algorithm:        None,
+        };
+        assert_eq!(job.rolled_version(0xFFFF_FFFF), 0x2000_0004);
+
+        job.version_mask = 0x1FFF_E000;
+        assert_eq!(job.rolled_version(0xFFFF_FFFF), 0x3FFF_E004);
+        assert_eq!(job.rolled_version(0x0000_2000), 0x2000_2004);
+    }
+
+    #[test]
+    fn test_nonce_increment_overflow() {
+        let mut nonce = Nonce(u32::MAX - 1);
+        assert_eq!(nonce.increment(), Some(u32::MAX));
+        assert_eq!(nonce.increment(), None);
+        assert_eq!(nonce.0, u32::MAX);
+    }
+
+    #[test]
+    fn test_reject_reason_classification() {
+        assert_eq!(
+            ShareRejectReason::from_stratum(21, "Job not found"),
+            ShareRejectReason::JobNotFound
+        );
+        assert_eq!(ShareRejectReason::from_stratum(22, ""), ShareRejectReason::Duplicate);
+        assert_eq!(ShareRejectReason::from_stratum(20, "Stale share"), ShareRejectReason::Stale);
+        assert_eq!(
+            ShareRejectReason::from_stratum(20, "low difficulty share of 0.5"),
+            ShareRejectReason::LowDifficulty
+        );
+        assert!(matches!(
+            ShareRejectReason::from_stratum(-1, "Bad nonce"),
+            ShareRejectReason::Other { code: -1, .. }
+        ));
+    }
+}
@@ -3,8 +3,13 @@
 //! This module contains all type definitions for the Mining plugin:
 //! - Mining statistics and job types
 //! - Block header and hash target structures
-//! - Pool connection state
+//! - Found shares, pool connection state and share outcomes
+//! - Pause events
+//! - Hybrid core classes and placement preferences
 
 mod core;
 
-pub use core::{BlockHeader, HashTarget, MiningJob, MiningStats, Nonce, PoolConnection};
+pub use core::{
+    BlockHeader, CoinbaseParts, CoreClass, CorePreference, FoundShare, HashTarget, MiningJob,
+    MiningStats, Nonce, PauseEvent, PauseReason, PoolConnection, ShareOutcome, ShareRejectReason,
+};